@@ -1,5 +1,7 @@
 use super::consts::*;
+use crate::edge::FillRule;
 use crate::polygon::*;
+use crate::renderer::{Light, Renderer};
 use crate::utils::*;
 use egui::*;
 
@@ -8,47 +10,57 @@ pub mod ui;
 
 pub struct PolygonFiller {
     coeff_data: CoeffData,
-    light_rgb: [f32; 3],
     object_rgb: [f32; 3],
     object_texture: image::Rgb32FImage,
     rotation: bool,
-    interpolation: InterpolationType,
+    renderer: Renderer,
     color_source: ColorSourceType,
     normal_map_enabled: bool,
     normal_map: image::Rgb32FImage,
-    sun_position_angle: f32,
-    sun_position_radius: f32,
+    lights: Vec<Light>,
     object: Vec<Polygon>,
+    uniform_scale: bool,
+    /// Rule of the outline fill preview, mesh triangles look the same under
+    /// either rule.
+    fill_rule: FillRule,
+    fill_preview: Option<TextureHandle>,
+    /// Last rendered frame, re-rendered only when `dirty` is set.
+    frame: Option<TextureHandle>,
+    dirty: bool,
 }
 
 impl Default for PolygonFiller {
     fn default() -> Self {
         Self {
             coeff_data: CoeffData {
+                ka: MAX_KA / 10.0,
                 kd: MAX_KD / 2.0,
                 ks: MAX_KS / 2.0,
                 m: MAX_M / 2.0,
-                z: MAX_Z / 2.0,
+                metalness: 0.0,
+                roughness: 0.5,
             },
-            light_rgb: [1.0, 1.0, 1.0],
             object_rgb: [1.0, 1.0, 1.0],
             object_texture: load_image("assets/texture.jpg"),
             rotation: false,
-            interpolation: InterpolationType::Vector,
+            renderer: Renderer::new(IMAGE_SIZE + 1, IMAGE_SIZE + 1),
             color_source: ColorSourceType::Color,
             normal_map_enabled: false,
             normal_map: load_image("assets/normal_map.png"),
-            sun_position_angle: 0.0,
-            sun_position_radius: ORBIT_R,
+            lights: vec![default_light()],
             object: load_polygons("assets/sphere.obj"),
+            uniform_scale: true,
+            fill_rule: FillRule::EvenOdd,
+            fill_preview: None,
+            frame: None,
+            dirty: true,
         }
     }
 }
 
-#[derive(PartialEq, Eq)]
-pub enum InterpolationType {
-    Color,
-    Vector,
+/// White point light above the model, the starting point for new lights.
+fn default_light() -> Light {
+    Light::on_orbit(0.0, ORBIT_R, MAX_Z / 2.0, [1.0, 1.0, 1.0])
 }
 
 #[derive(PartialEq, Eq)]
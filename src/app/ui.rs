@@ -1,6 +1,14 @@
-use super::{ColorSourceType, InterpolationType, PolygonFiller};
+use super::painter::fill_rule_preview;
+use super::{default_light, ColorSourceType, PolygonFiller};
 use crate::app::CollapsingHeader;
 use crate::consts::*;
+use crate::edge::FillRule;
+use crate::math::Vector3;
+use crate::renderer::{
+    Attenuation, AttenuationModel, CullMode, DepthTest, DisplayMode, FrontFace, InterpolationType,
+    Light, LightType, Rasterizer, ReflectionModel, Renderer, Supersampling,
+};
+use crate::transform::Transform;
 use crate::utils::*;
 use egui::*;
 
@@ -14,18 +22,37 @@ impl eframe::App for PolygonFiller {
                 let time = chrono::Local::now().time();
                 let sec_since_midnight =
                     time.num_seconds_from_midnight() as f64 + 1e-9 * (time.nanosecond() as f64);
-                self.sun_position_angle =
+                let angle =
                     (sec_since_midnight * 2.5).rem_euclid(2.0 * std::f64::consts::PI) as f32;
-                let r = (sec_since_midnight * 20.0).rem_euclid(ORBIT_R as f64 * 2f64);
-                self.sun_position_radius = if (r as f32) < ORBIT_R {
+                let r =
+                    (sec_since_midnight * ORBIT_R as f64 / 25.0).rem_euclid(ORBIT_R as f64 * 2f64);
+                let radius = if (r as f32) < ORBIT_R {
                     r as f32
                 } else {
                     2f32 * ORBIT_R - r as f32
                 };
+                // the animation moves the first light, keeping its height
+                if let Some(light) = self.lights.first_mut() {
+                    light.position = Light::orbit_position(angle, radius, light.position.z);
+                }
+                self.dirty = true;
                 ui.ctx().request_repaint();
             }
 
-            let bitmap = self.paint();
+            if self.dirty || self.frame.is_none() {
+                let bitmap = self.paint();
+                match &mut self.frame {
+                    Some(texture) => texture.set(bitmap, egui::TextureFilter::Linear),
+                    None => {
+                        self.frame = Some(ui.ctx().load_texture(
+                            "sphere",
+                            bitmap,
+                            egui::TextureFilter::Linear,
+                        ))
+                    }
+                }
+                self.dirty = false;
+            }
 
             let window_size = ui.available_size();
             let mut img_ui = ui.child_ui(
@@ -42,16 +69,24 @@ impl eframe::App for PolygonFiller {
                 egui::Layout::left_to_right(egui::Align::RIGHT),
             );
 
-            let texture = &img_ui
-                .ctx()
-                .load_texture("sphere", bitmap, egui::TextureFilter::Linear);
-            img_ui.image(texture, texture.size_vec2());
+            let texture = self.frame.as_ref().unwrap();
+            let response = img_ui
+                .add(egui::Image::new(texture, texture.size_vec2()).sense(Sense::click_and_drag()));
+            self.camera_controls(ui, &response);
 
             Frame::popup(ui.style())
                 .stroke(Stroke::none())
                 .show(ui, |ui| {
                     ui.set_max_width(270.0);
-                    CollapsingHeader::new("Settings").show(ui, |ui| self.options_ui(ui));
+                    CollapsingHeader::new("Settings").show(ui, |ui| {
+                        ScrollArea::vertical()
+                            .max_height(IMAGE_SIZE as f32)
+                            .show(ui, |ui| {
+                                if self.options_ui(ui) {
+                                    self.dirty = true;
+                                }
+                            });
+                    });
                 });
         });
     }
@@ -62,78 +97,584 @@ impl PolygonFiller {
         Default::default()
     }
 
-    fn options_ui(&mut self, ui: &mut Ui) {
+    /// Left drag orbits the camera around its target, right or middle drag
+    /// pans it and scrolling zooms in and out.
+    fn camera_controls(&mut self, ui: &Ui, response: &Response) {
+        let camera = &mut self.renderer.camera;
+        let delta = response.drag_delta();
+        if delta != Vec2::ZERO {
+            self.dirty = true;
+        }
+        if response.dragged_by(PointerButton::Primary) {
+            camera.orbit(
+                -delta.x * CAMERA_ROTATION_SPEED,
+                delta.y * CAMERA_ROTATION_SPEED,
+            );
+        } else if response.dragged_by(PointerButton::Secondary)
+            || response.dragged_by(PointerButton::Middle)
+        {
+            camera.pan(delta.x, delta.y, IMAGE_SIZE as f32);
+        }
+
+        if response.hovered() {
+            let scroll = ui.input().scroll_delta.y;
+            if scroll != 0.0 {
+                camera.zoom((-scroll * CAMERA_ZOOM_SPEED).exp());
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Draws the settings panel, returns whether any setting affecting the
+    /// rendered image changed.
+    fn options_ui(&mut self, ui: &mut Ui) -> bool {
         let Self {
             coeff_data,
-            light_rgb,
+            lights,
             object_rgb,
             object_texture,
             rotation,
-            interpolation,
+            renderer,
             color_source,
             normal_map,
             normal_map_enabled,
             object,
+            uniform_scale,
+            fill_rule,
+            fill_preview,
             ..
         } = self;
 
-        let CoeffData { kd, ks, m, z } = coeff_data;
+        let CoeffData {
+            ka,
+            kd,
+            ks,
+            m,
+            metalness,
+            roughness,
+        } = coeff_data;
+        let Renderer {
+            interpolation,
+            reflection_model,
+            depth_test,
+            parallel,
+            cull_mode,
+            front_face,
+            supersampling,
+            rasterizer,
+            display_mode,
+            wireframe_color,
+            wireframe_width,
+            camera,
+            transform,
+            ambient,
+            shadows,
+            ..
+        } = renderer;
+        let mut changed = false;
 
         ui.add(egui::Checkbox::new(&mut *rotation, "Enable animation"));
+        changed |= ui
+            .add(egui::Checkbox::new(
+                &mut *parallel,
+                "Multi-threaded rendering",
+            ))
+            .changed();
 
         if ui.add(egui::Button::new("Load new model")).clicked() {
             let new_obj = load_obj();
             if !new_obj.is_empty() {
-                *object = new_obj
+                *object = new_obj;
+                changed = true;
             }
         }
 
         ui.separator();
         ui.label("Coefficients");
-        ui.add(egui::Slider::new(m, 1f32..=MAX_M).text("m"));
-        ui.add(egui::Slider::new(z, (MAX_Z / 2.0)..=MAX_Z).text("z"));
-        ui.add(egui::Slider::new(kd, 0.001..=MAX_KD).text("kd"));
-        ui.add(egui::Slider::new(ks, 0.001..=MAX_KS).text("ks"));
+        changed |= ui
+            .add(egui::Slider::new(m, 1f32..=MAX_M).text("m"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(ka, 0.0..=MAX_KA).text("ka"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(kd, 0.001..=MAX_KD).text("kd"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(ks, 0.001..=MAX_KS).text("ks"))
+            .changed();
 
         ui.separator();
         ui.label("Interpolation");
-        ui.radio_value(&mut *interpolation, InterpolationType::Color, "color");
-        ui.radio_value(&mut *interpolation, InterpolationType::Vector, "vector");
+        changed |= ui
+            .radio_value(&mut *interpolation, InterpolationType::Flat, "flat")
+            .changed();
+        changed |= ui
+            .radio_value(&mut *interpolation, InterpolationType::Color, "color")
+            .changed();
+        changed |= ui
+            .radio_value(&mut *interpolation, InterpolationType::Vector, "vector")
+            .changed();
 
         ui.separator();
-        ui.label("Colors and textures");
+        ui.label("Reflection model");
+        ui.horizontal_wrapped(|ui| {
+            changed |= ui
+                .radio_value(&mut *reflection_model, ReflectionModel::Lambert, "Lambert")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *reflection_model, ReflectionModel::Phong, "Phong")
+                .changed();
+            changed |= ui
+                .radio_value(
+                    &mut *reflection_model,
+                    ReflectionModel::BlinnPhong,
+                    "Blinn-Phong",
+                )
+                .changed();
+            changed |= ui
+                .radio_value(&mut *reflection_model, ReflectionModel::Gaussian, "Gauss")
+                .changed();
+            changed |= ui
+                .radio_value(
+                    &mut *reflection_model,
+                    ReflectionModel::CookTorrance,
+                    "Cook-Torrance",
+                )
+                .changed();
+        });
+        if *reflection_model == ReflectionModel::CookTorrance {
+            changed |= ui
+                .add(egui::Slider::new(metalness, 0.0..=1.0).text("metalness"))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(roughness, 0.05..=1.0).text("roughness"))
+                .changed();
+        }
 
+        ui.separator();
+        ui.label("Rasterizer");
         ui.horizontal(|ui| {
-            ui.color_edit_button_rgb(&mut *light_rgb);
-            ui.label(": choose light color");
+            changed |= ui
+                .radio_value(&mut *rasterizer, Rasterizer::Scanline, "scanline")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *rasterizer, Rasterizer::HalfSpace, "half-space")
+                .changed();
         });
+        let mut rule_changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Fill rule:");
+            rule_changed |= ui
+                .radio_value(&mut *fill_rule, FillRule::EvenOdd, "even-odd")
+                .changed();
+            rule_changed |= ui
+                .radio_value(&mut *fill_rule, FillRule::NonZero, "non-zero")
+                .changed();
+        });
+        let preview = fill_preview.get_or_insert_with(|| {
+            ui.ctx().load_texture(
+                "fill rule",
+                fill_rule_preview(*fill_rule),
+                egui::TextureFilter::Nearest,
+            )
+        });
+        if rule_changed {
+            preview.set(fill_rule_preview(*fill_rule), egui::TextureFilter::Nearest);
+        }
+        ui.add(egui::Image::new(&*preview, preview.size_vec2()))
+            .on_hover_text("Self-intersecting outlines filled by the scanline algorithm, mesh triangles look the same under both rules");
 
+        ui.separator();
+        ui.label("Depth test");
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut *depth_test, DepthTest::Greater, ">")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *depth_test, DepthTest::GreaterEqual, ">=")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *depth_test, DepthTest::Less, "<")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *depth_test, DepthTest::LessEqual, "<=")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *depth_test, DepthTest::Always, "always")
+                .changed();
+        });
+
+        ui.separator();
+        ui.label("Face culling");
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut *cull_mode, CullMode::None, "none")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *cull_mode, CullMode::Back, "back")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *cull_mode, CullMode::Front, "front")
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Front faces:");
+            changed |= ui
+                .radio_value(&mut *front_face, FrontFace::Clockwise, "CW")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *front_face, FrontFace::CounterClockwise, "CCW")
+                .changed();
+        });
+
+        ui.separator();
+        ui.label("Display");
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut *display_mode, DisplayMode::Shaded, "shaded")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *display_mode, DisplayMode::Wireframe, "wireframe")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *display_mode, DisplayMode::Overlay, "overlay")
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            changed |= ui.color_edit_button_rgb(&mut *wireframe_color).changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut *wireframe_width, 0.5..=5.0).text("edge width"))
+                .changed();
+        });
+
+        ui.separator();
+        ui.label("Anti-aliasing");
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut *supersampling, Supersampling::Off, "off")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *supersampling, Supersampling::Grid2x2, "2x2")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *supersampling, Supersampling::Grid3x3, "3x3")
+                .changed();
+            changed |= ui
+                .radio_value(&mut *supersampling, Supersampling::Grid4x4, "4x4")
+                .changed();
+        });
+
+        ui.separator();
+        ui.label("Camera");
+        changed |= vector_ui(ui, "position", &mut camera.position, 0.05);
+        changed |= vector_ui(ui, "target", &mut camera.target, 0.05);
+        changed |= ui
+            .add(egui::Slider::new(&mut camera.fov, 10.0..=120.0).text("fov"))
+            .changed();
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut camera.near)
+                        .speed(0.01)
+                        .clamp_range(0.01..=camera.far - MIN_DEPTH_RANGE),
+                )
+                .changed();
+            ui.label("near");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut camera.far)
+                        .speed(0.1)
+                        .clamp_range(camera.near + MIN_DEPTH_RANGE..=1000.0),
+                )
+                .changed();
+            ui.label("far");
+        });
+        camera.sanitize();
+
+        ui.separator();
         ui.horizontal(|ui| {
-            ui.radio_value(&mut *color_source, ColorSourceType::Texture, "Texture");
+            ui.label("Transform");
+            if ui.add(egui::Button::new("Reset")).clicked() {
+                *transform = Transform::default();
+                changed = true;
+            }
+        });
+        changed |= vector_ui(ui, "translation", &mut transform.translation, 0.05);
+        changed |= vector_ui(ui, "rotation", &mut transform.rotation, 1.0);
+        ui.horizontal(|ui| {
+            if *uniform_scale {
+                changed |= ui
+                    .add(egui::DragValue::new(&mut transform.scale.x).speed(0.01))
+                    .changed();
+            } else {
+                changed |= vector_ui(ui, "", &mut transform.scale, 0.01);
+            }
+            changed |= ui
+                .add(egui::Checkbox::new(&mut *uniform_scale, "uniform scale"))
+                .changed();
+        });
+        let scale = &mut transform.scale;
+        if *uniform_scale && (scale.y, scale.z) != (scale.x, scale.x) {
+            (scale.y, scale.z) = (scale.x, scale.x);
+            changed = true;
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Lights");
+            if ui.add(egui::Button::new("Add light")).clicked() {
+                lights.push(default_light());
+                changed = true;
+            }
+        });
+        let mut removed = None;
+        for (i, light) in lights.iter_mut().enumerate() {
+            CollapsingHeader::new(format!("Light {}", i + 1))
+                .id_source(("light", i))
+                .show(ui, |ui| {
+                    changed |= light_ui(ui, light, transform.translation);
+                    if ui.add(egui::Button::new("Remove")).clicked() {
+                        removed = Some(i);
+                    }
+                });
+        }
+        if let Some(i) = removed {
+            lights.remove(i);
+            changed = true;
+        }
+
+        ui.separator();
+        ui.label("Ambient light");
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(egui::Checkbox::new(&mut ambient.hemispheric, "hemispheric"))
+                .changed();
+            if ambient.hemispheric {
+                changed |= ui.color_edit_button_rgb(&mut ambient.sky).changed();
+                ui.label("sky");
+                changed |= ui.color_edit_button_rgb(&mut ambient.ground).changed();
+                ui.label("ground");
+            } else {
+                changed |= ui.color_edit_button_rgb(&mut ambient.color).changed();
+                ui.label("color");
+            }
+        });
+
+        ui.separator();
+        ui.label("Shadows");
+        changed |= ui
+            .add(egui::Checkbox::new(&mut shadows.enabled, "cast shadows"))
+            .changed();
+        if shadows.enabled {
+            ui.horizontal(|ui| {
+                for size in [512, 1024, 2048] {
+                    changed |= ui
+                        .radio_value(&mut shadows.map_size, size, size.to_string())
+                        .changed();
+                }
+                ui.label("depth map");
+            });
+            changed |= ui
+                .add(egui::Slider::new(&mut shadows.bias, 0.0..=0.05).text("bias"))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut shadows.pcf_radius, 0..=3).text("PCF radius"))
+                .changed();
+        }
+
+        ui.separator();
+        ui.label("Colors and textures");
+
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut *color_source, ColorSourceType::Texture, "Texture")
+                .changed();
             if ui.add(egui::Button::new("Load new texture")).clicked() {
                 let new_image = load_texture();
                 if let Some(i) = new_image {
                     *object_texture = i;
+                    changed = true;
                 }
             }
         });
 
         ui.horizontal(|ui| {
-            ui.radio_value(&mut *color_source, ColorSourceType::Color, "Color");
-            ui.color_edit_button_rgb(&mut *object_rgb);
+            changed |= ui
+                .radio_value(&mut *color_source, ColorSourceType::Color, "Color")
+                .changed();
+            changed |= ui.color_edit_button_rgb(&mut *object_rgb).changed();
         });
 
         ui.horizontal(|ui| {
-            ui.add(egui::Checkbox::new(
-                &mut *normal_map_enabled,
-                "Enable custom normal map",
-            ));
+            changed |= ui
+                .add(egui::Checkbox::new(
+                    &mut *normal_map_enabled,
+                    "Enable custom normal map",
+                ))
+                .changed();
             if ui.add(egui::Button::new("Load normal map")).clicked() {
                 let new_normal_map = load_texture();
                 if let Some(i) = new_normal_map {
                     *normal_map = i;
+                    changed = true;
                 }
             }
         });
+        changed
+    }
+}
+
+/// Editor of a single light, returns whether it changed. `model_center` is
+/// where the "Aim at model" button points the light.
+fn light_ui(ui: &mut Ui, light: &mut Light, model_center: Vector3) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui
+            .radio_value(&mut light.light_type, LightType::Point, "point")
+            .changed();
+        changed |= ui
+            .radio_value(&mut light.light_type, LightType::Directional, "directional")
+            .changed();
+        changed |= ui
+            .radio_value(&mut light.light_type, LightType::Spot, "spot")
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        changed |= ui.color_edit_button_rgb(&mut light.color).changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut light.intensity, 0.0..=5.0).text("intensity"))
+            .changed();
+    });
+    if light.light_type != LightType::Directional {
+        changed |= vector_ui(ui, "position", &mut light.position, 0.05);
+        changed |= attenuation_ui(ui, &mut light.attenuation);
+    }
+    if light.light_type != LightType::Point {
+        changed |= direction_ui(ui, &mut light.direction);
+    }
+    if light.light_type == LightType::Spot {
+        if ui.add(egui::Button::new("Aim at model")).clicked() {
+            light.direction = model_center - light.position;
+            changed = true;
+        }
+        changed |= ui
+            .add(egui::Slider::new(&mut light.inner_cone, 0.0..=90.0).text("inner cone"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut light.outer_cone, 0.0..=90.0).text("outer cone"))
+            .changed();
+        light.outer_cone = light.outer_cone.max(light.inner_cone);
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut light.falloff, 0.1..=10.0)
+                    .logarithmic(true)
+                    .text("falloff"),
+            )
+            .changed();
     }
+    changed
+}
+
+fn attenuation_ui(ui: &mut Ui, attenuation: &mut Attenuation) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Attenuation:");
+        changed |= ui
+            .radio_value(&mut attenuation.model, AttenuationModel::None, "none")
+            .changed();
+        changed |= ui
+            .radio_value(
+                &mut attenuation.model,
+                AttenuationModel::Polynomial,
+                "c/l/q",
+            )
+            .changed();
+        changed |= ui
+            .radio_value(
+                &mut attenuation.model,
+                AttenuationModel::InverseSquare,
+                "1/d²",
+            )
+            .changed();
+    });
+    match attenuation.model {
+        AttenuationModel::None => {}
+        AttenuationModel::Polynomial => {
+            ui.horizontal(|ui| {
+                for (value, prefix) in [
+                    (&mut attenuation.constant, "c: "),
+                    (&mut attenuation.linear, "l: "),
+                    (&mut attenuation.quadratic, "q: "),
+                ] {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(value)
+                                .speed(0.01)
+                                .clamp_range(0.0..=10.0)
+                                .prefix(prefix),
+                        )
+                        .changed();
+                }
+            });
+        }
+        AttenuationModel::InverseSquare => {
+            changed |= ui
+                .add(egui::Slider::new(&mut attenuation.range, 0.5..=20.0).text("range"))
+                .changed();
+        }
+    }
+    changed
+}
+
+/// Edits a direction as yaw around the y axis and pitch above the xz plane,
+/// both in degrees, returns whether it changed.
+fn direction_ui(ui: &mut Ui, direction: &mut Vector3) -> bool {
+    let d = direction.norm();
+    let mut yaw = d.x.atan2(d.z).to_degrees();
+    let mut pitch = d.y.clamp(-1.0, 1.0).asin().to_degrees();
+    let changed = ui
+        .horizontal(|ui| {
+            let yaw_changed = ui
+                .add(egui::DragValue::new(&mut yaw).speed(1.0).prefix("yaw: "))
+                .changed();
+            let pitch_changed = ui
+                .add(
+                    egui::DragValue::new(&mut pitch)
+                        .speed(1.0)
+                        .clamp_range(-90.0..=90.0)
+                        .prefix("pitch: "),
+                )
+                .changed();
+            ui.label("direction");
+            yaw_changed || pitch_changed
+        })
+        .inner;
+    if changed {
+        let (yaw, pitch) = (yaw.to_radians(), pitch.to_radians());
+        *direction = Vector3::new(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            pitch.cos() * yaw.cos(),
+        );
+    }
+    changed
+}
+
+fn vector_ui(ui: &mut Ui, label: &str, vector: &mut Vector3, speed: f32) -> bool {
+    ui.horizontal(|ui| {
+        let changed = [
+            (&mut vector.x, "x: "),
+            (&mut vector.y, "y: "),
+            (&mut vector.z, "z: "),
+        ]
+        .map(|(value, prefix)| {
+            ui.add(egui::DragValue::new(value).speed(speed).prefix(prefix))
+                .changed()
+        })
+        .contains(&true);
+        ui.label(label);
+        changed
+    })
+    .inner
 }
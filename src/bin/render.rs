@@ -0,0 +1,338 @@
+#![warn(clippy::all, rust_2018_idioms)]
+use polygon_filler::camera::Camera;
+use polygon_filler::consts::*;
+use polygon_filler::math::Vector3;
+use polygon_filler::renderer::{
+    Ambient, Attenuation, AttenuationModel, DisplayMode, InterpolationType, Light, Material,
+    Rasterizer, ReflectionModel, Renderer, Supersampling,
+};
+use polygon_filler::shadow::Shadows;
+use polygon_filler::transform::Transform;
+use polygon_filler::utils::{load_polygons, read_image};
+use std::process::exit;
+
+const USAGE: &str = "usage: render <model.obj> -o <output.png> [options]
+
+options:
+    --size <px>              width and height of the output image
+    --texture <path>         object texture, replaces --color
+    --normal-map <path>      normal map applied on top of the mesh normals
+    --color <r,g,b>          object color, components in 0..=1
+    --ka <value>             ambient coefficient
+    --kd <value>             diffuse coefficient
+    --ks <value>             specular coefficient
+    --m <value>              specular exponent
+    --z <value>              height of the light above the model center
+    --light-color <r,g,b>    light color, components in 0..=1
+    --ambient <r,g,b>        ambient light color
+    --sky <r,g,b>            hemispheric ambient color for normals facing up
+    --ground <r,g,b>         hemispheric ambient color for normals facing down
+    --sun-angle <rad>        light angle around the z axis
+    --sun-radius <value>     light distance from the z axis
+    --light-intensity <v>    light intensity multiplier
+    --attenuation <c,l,q>    light fades as 1 / (c + l*d + q*d^2) with distance d
+    --light-range <value>    light fades as 1 / d^2 and vanishes at the given range
+    --shadows <px>           enable shadows using depth maps of the given size
+    --shadow-bias <value>    depth offset preventing surfaces from shadowing themselves
+    --pcf <radius>           shadow edge softening radius in depth map texels
+    --camera <x,y,z>         camera position, the model fits in the -1..1 cube
+    --target <x,y,z>         point the camera looks at
+    --fov <deg>              vertical field of view
+    --translate <x,y,z>      object translation
+    --rotate <x,y,z>         object rotation around the x, y and z axes in degrees
+    --scale <s|x,y,z>        uniform or per-axis object scale
+    --interpolation <mode>   `flat`, `color` or `vector`
+    --reflection <model>     `lambert`, `phong`, `blinn-phong`, `gaussian` or `cook-torrance`
+    --metalness <value>      Cook-Torrance metalness in 0..=1
+    --roughness <value>      Cook-Torrance roughness in 0..=1
+    --supersampling <n>      anti-aliasing grid of n x n samples per pixel, n in 1..=4
+    --rasterizer <name>      `scanline` or `halfspace`
+    --display <mode>         `shaded`, `wireframe` or `overlay`
+    --wire-color <r,g,b>     wireframe color, components in 0..=1
+    --wire-width <px>        wireframe line thickness";
+
+struct Args {
+    model: String,
+    output: String,
+    size: u32,
+    texture: Option<String>,
+    normal_map: Option<String>,
+    color: [f32; 3],
+    ka: f32,
+    kd: f32,
+    ks: f32,
+    m: f32,
+    z: f32,
+    metalness: f32,
+    roughness: f32,
+    light_color: [f32; 3],
+    sun_angle: f32,
+    sun_radius: f32,
+    light_intensity: f32,
+    attenuation: Attenuation,
+    ambient: Ambient,
+    shadows: Shadows,
+    camera: Camera,
+    transform: Transform,
+    interpolation: InterpolationType,
+    reflection: ReflectionModel,
+    supersampling: Supersampling,
+    rasterizer: Rasterizer,
+    display_mode: DisplayMode,
+    wire_color: [f32; 3],
+    wire_width: f32,
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<f32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: `{}`", flag, value))
+}
+
+fn parse_triple(flag: &str, value: &str) -> Result<[f32; 3], String> {
+    let components = value
+        .split(',')
+        .map(|c| parse_number(flag, c.trim()))
+        .collect::<Result<Vec<f32>, String>>()?;
+    match components[..] {
+        [r, g, b] => Ok([r, g, b]),
+        _ => Err(format!("{} expects three comma separated values", flag)),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut model = None;
+    let mut output = None;
+    let mut parsed = Args {
+        model: String::new(),
+        output: String::new(),
+        size: IMAGE_SIZE + 1,
+        texture: None,
+        normal_map: None,
+        color: [1.0, 1.0, 1.0],
+        ka: MAX_KA / 10.0,
+        kd: MAX_KD / 2.0,
+        ks: MAX_KS / 2.0,
+        m: MAX_M / 2.0,
+        z: MAX_Z / 2.0,
+        metalness: 0.0,
+        roughness: 0.5,
+        light_color: [1.0, 1.0, 1.0],
+        sun_angle: 0.0,
+        sun_radius: ORBIT_R,
+        light_intensity: 1.0,
+        attenuation: Attenuation::default(),
+        ambient: Ambient::default(),
+        shadows: Shadows::default(),
+        camera: Camera::default(),
+        transform: Transform::default(),
+        interpolation: InterpolationType::Vector,
+        reflection: ReflectionModel::Phong,
+        supersampling: Supersampling::Off,
+        rasterizer: Rasterizer::Scanline,
+        display_mode: DisplayMode::Shaded,
+        wire_color: [1.0, 1.0, 1.0],
+        wire_width: 1.0,
+    };
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            if model.replace(arg).is_some() {
+                return Err("only one model can be rendered at a time".to_string());
+            }
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(value),
+            "--size" => {
+                parsed.size = value
+                    .parse()
+                    .ok()
+                    .filter(|size| *size > 1)
+                    .ok_or_else(|| format!("invalid value for --size: `{}`", value))?
+            }
+            "--texture" => parsed.texture = Some(value),
+            "--normal-map" => parsed.normal_map = Some(value),
+            "--color" => parsed.color = parse_triple(&arg, &value)?,
+            "--ka" => parsed.ka = parse_number(&arg, &value)?,
+            "--kd" => parsed.kd = parse_number(&arg, &value)?,
+            "--ks" => parsed.ks = parse_number(&arg, &value)?,
+            "--m" => parsed.m = parse_number(&arg, &value)?,
+            "--z" => parsed.z = parse_number(&arg, &value)?,
+            "--metalness" => parsed.metalness = parse_number(&arg, &value)?,
+            "--roughness" => parsed.roughness = parse_number(&arg, &value)?,
+            "--light-color" => parsed.light_color = parse_triple(&arg, &value)?,
+            "--sun-angle" => parsed.sun_angle = parse_number(&arg, &value)?,
+            "--sun-radius" => parsed.sun_radius = parse_number(&arg, &value)?,
+            "--light-intensity" => parsed.light_intensity = parse_number(&arg, &value)?,
+            "--attenuation" => {
+                let [constant, linear, quadratic] = parse_triple(&arg, &value)?;
+                parsed.attenuation = Attenuation {
+                    model: AttenuationModel::Polynomial,
+                    constant,
+                    linear,
+                    quadratic,
+                    ..parsed.attenuation
+                }
+            }
+            "--light-range" => {
+                parsed.attenuation.model = AttenuationModel::InverseSquare;
+                parsed.attenuation.range = parse_number(&arg, &value)?;
+            }
+            "--ambient" => parsed.ambient.color = parse_triple(&arg, &value)?,
+            "--sky" => {
+                parsed.ambient.hemispheric = true;
+                parsed.ambient.sky = parse_triple(&arg, &value)?;
+            }
+            "--ground" => {
+                parsed.ambient.hemispheric = true;
+                parsed.ambient.ground = parse_triple(&arg, &value)?;
+            }
+            "--shadows" => {
+                parsed.shadows.enabled = true;
+                parsed.shadows.map_size = value
+                    .parse()
+                    .ok()
+                    .filter(|size| (1..=8192).contains(size))
+                    .ok_or(format!("invalid shadow map size `{}`", value))?;
+            }
+            "--shadow-bias" => parsed.shadows.bias = parse_number(&arg, &value)?,
+            "--pcf" => {
+                parsed.shadows.pcf_radius = value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: `{}`", arg, value))?
+            }
+            "--camera" => parsed.camera.position = Vector3::from_array(parse_triple(&arg, &value)?),
+            "--target" => parsed.camera.target = Vector3::from_array(parse_triple(&arg, &value)?),
+            "--fov" => parsed.camera.fov = parse_number(&arg, &value)?,
+            "--translate" => {
+                parsed.transform.translation = Vector3::from_array(parse_triple(&arg, &value)?)
+            }
+            "--rotate" => {
+                parsed.transform.rotation = Vector3::from_array(parse_triple(&arg, &value)?)
+            }
+            "--scale" => {
+                parsed.transform.scale = Vector3::from_array(match value.contains(',') {
+                    true => parse_triple(&arg, &value)?,
+                    false => [parse_number(&arg, &value)?; 3],
+                })
+            }
+            "--interpolation" => {
+                parsed.interpolation = match value.as_str() {
+                    "flat" => InterpolationType::Flat,
+                    "color" => InterpolationType::Color,
+                    "vector" => InterpolationType::Vector,
+                    _ => return Err(format!("unknown interpolation `{}`", value)),
+                }
+            }
+            "--reflection" => {
+                parsed.reflection = match value.as_str() {
+                    "lambert" => ReflectionModel::Lambert,
+                    "phong" => ReflectionModel::Phong,
+                    "blinn-phong" => ReflectionModel::BlinnPhong,
+                    "gaussian" => ReflectionModel::Gaussian,
+                    "cook-torrance" => ReflectionModel::CookTorrance,
+                    _ => return Err(format!("unknown reflection model `{}`", value)),
+                }
+            }
+            "--supersampling" => {
+                parsed.supersampling = match value.as_str() {
+                    "1" => Supersampling::Off,
+                    "2" => Supersampling::Grid2x2,
+                    "3" => Supersampling::Grid3x3,
+                    "4" => Supersampling::Grid4x4,
+                    _ => return Err(format!("invalid value for --supersampling: `{}`", value)),
+                }
+            }
+            "--rasterizer" => {
+                parsed.rasterizer = match value.as_str() {
+                    "scanline" => Rasterizer::Scanline,
+                    "halfspace" => Rasterizer::HalfSpace,
+                    _ => return Err(format!("unknown rasterizer `{}`", value)),
+                }
+            }
+            "--display" => {
+                parsed.display_mode = match value.as_str() {
+                    "shaded" => DisplayMode::Shaded,
+                    "wireframe" => DisplayMode::Wireframe,
+                    "overlay" => DisplayMode::Overlay,
+                    _ => return Err(format!("unknown display mode `{}`", value)),
+                }
+            }
+            "--wire-color" => parsed.wire_color = parse_triple(&arg, &value)?,
+            "--wire-width" => parsed.wire_width = parse_number(&arg, &value)?,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    if parsed.camera.position == parsed.camera.target {
+        return Err("the camera position and target must differ".to_string());
+    }
+    parsed.model = model.ok_or("missing model path")?;
+    parsed.output = output.ok_or("missing output path")?;
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mesh = load_polygons(&args.model);
+    if mesh.is_empty() {
+        return Err(format!("could not load model from {}", args.model));
+    }
+    let load = |path: &Option<String>| {
+        path.as_ref()
+            .map(|p| read_image(p, args.size).map_err(|e| format!("could not load {}: {}", p, e)))
+            .transpose()
+    };
+    let texture = load(&args.texture)?;
+    let normal_map = load(&args.normal_map)?;
+
+    let material = Material {
+        ka: args.ka,
+        kd: args.kd,
+        ks: args.ks,
+        m: args.m,
+        color: args.color,
+        texture: texture.as_ref(),
+        normal_map: normal_map.as_ref(),
+        metalness: args.metalness,
+        roughness: args.roughness,
+    };
+    let mut light = Light::on_orbit(args.sun_angle, args.sun_radius, args.z, args.light_color);
+    light.intensity = args.light_intensity;
+    light.attenuation = args.attenuation;
+    let lights = [light];
+    let mut renderer = Renderer::new(args.size, args.size);
+    renderer.ambient = args.ambient;
+    renderer.shadows = args.shadows;
+    renderer.camera = args.camera;
+    renderer.transform = args.transform;
+    renderer.interpolation = args.interpolation;
+    renderer.reflection_model = args.reflection;
+    renderer.supersampling = args.supersampling;
+    renderer.rasterizer = args.rasterizer;
+    renderer.display_mode = args.display_mode;
+    renderer.wireframe_color = args.wire_color;
+    renderer.wireframe_width = args.wire_width;
+
+    renderer
+        .render(&mesh, &material, &lights)
+        .save(&args.output)
+        .map_err(|e| format!("could not write {}: {}", args.output, e))
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+    if let Err(e) = run(args) {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+}
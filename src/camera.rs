@@ -0,0 +1,135 @@
+use crate::consts::MIN_DEPTH_RANGE;
+use crate::math::{Matrix4, Vector3};
+
+/// Perspective camera in the normalized model space produced by `load_polygons`.
+pub struct Camera {
+    pub position: Vector3,
+    pub target: Vector3,
+    pub up: Vector3,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 3.0),
+            target: Vector3::new(0.0, 0.0, 0.0),
+            up: Vector3::new(0.0, 1.0, 0.0),
+            fov: 40.0,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn view_matrix(&self) -> Matrix4 {
+        Matrix4::look_at(self.position, self.target, self.view_up())
+    }
+
+    /// `up`, or another axis when the camera looks along it and the view
+    /// orientation would be undefined.
+    fn view_up(&self) -> Vector3 {
+        let forward = (self.target - self.position).norm();
+        if forward.dot(self.up.norm()).abs() < 0.999 {
+            return self.up;
+        }
+        [Vector3::new(0.0, 0.0, -1.0), Vector3::new(1.0, 0.0, 0.0)]
+            .into_iter()
+            .find(|axis| forward.dot(*axis).abs() < 0.999)
+            .unwrap_or(self.up)
+    }
+
+    /// Repairs settings entered by hand that leave the view undefined: moves
+    /// the far plane behind the near one and steps the camera back from its
+    /// target when the two coincide.
+    pub fn sanitize(&mut self) {
+        self.far = self.far.max(self.near + MIN_DEPTH_RANGE);
+        let distance = (self.position - self.target).len();
+        if distance.is_nan() || distance < 1e-4 {
+            self.position = self.target + Vector3::new(0.0, 0.0, self.near * 2.0);
+        }
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Matrix4 {
+        Matrix4::perspective(self.fov.to_radians(), aspect, self.near, self.far)
+    }
+
+    /// Rotates the camera around its target by `yaw` and `pitch` radians,
+    /// keeping it off the poles so the up vector stays meaningful.
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        let offset = self.position - self.target;
+        let distance = offset.len();
+        let limit = 89f32.to_radians();
+        let yaw = offset.x.atan2(offset.z) + yaw;
+        let pitch = ((offset.y / distance).asin() + pitch).clamp(-limit, limit);
+        self.position = self.target
+            + Vector3::new(
+                pitch.cos() * yaw.sin(),
+                pitch.sin(),
+                pitch.cos() * yaw.cos(),
+            ) * distance;
+    }
+
+    /// Moves the camera and its target across the view plane. The offset is
+    /// given in pixels of a viewport `viewport_height` pixels tall, so the
+    /// point under the cursor follows it at the target's depth.
+    pub fn pan(&mut self, dx: f32, dy: f32, viewport_height: f32) {
+        let forward = (self.target - self.position).norm();
+        let right = forward.cross(self.view_up()).norm();
+        let up = right.cross(forward);
+        let scale = 2.0 * (self.target - self.position).len() * (self.fov.to_radians() / 2.0).tan()
+            / viewport_height;
+        let shift = right * (-dx * scale) + up * (dy * scale);
+        self.position = self.position + shift;
+        self.target = self.target + shift;
+    }
+
+    /// Scales the distance to the target by `factor`, staying inside the
+    /// near and far planes.
+    pub fn zoom(&mut self, factor: f32) {
+        let offset = self.position - self.target;
+        let closest = self.near * 2.0;
+        let distance = (offset.len() * factor).clamp(closest, (self.far / 2.0).max(closest));
+        self.position = self.target + offset.norm() * distance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_repairs_degenerate_views() {
+        let mut camera = Camera {
+            position: Vector3::new(1.0, 2.0, 3.0),
+            target: Vector3::new(1.0, 2.0, 3.0),
+            near: 0.5,
+            far: 0.5,
+            ..Camera::default()
+        };
+        camera.sanitize();
+        assert!(camera.far > camera.near);
+        assert!((camera.position - camera.target).len() > 0.0);
+        let view = camera.view_matrix();
+        assert!(view.m.iter().flatten().all(|v| v.is_finite()));
+
+        camera.position.x = f32::NAN;
+        camera.sanitize();
+        assert!(camera.position.x.is_finite());
+    }
+
+    #[test]
+    fn zoom_survives_narrow_depth_range() {
+        let mut camera = Camera {
+            near: 1.0,
+            far: 3.0,
+            ..Camera::default()
+        };
+        camera.zoom(0.5);
+        assert_eq!((camera.position - camera.target).len(), 2.0);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::math::Vector4;
+use crate::polygon::Vertex;
+
+/// Planes of the clip-space view volume `-w <= x, y, z <= w` as signed
+/// distance functions, non-negative on the visible side. The x and y planes
+/// bound the image rectangle, the z planes are the near and far planes.
+const PLANES: [fn(Vector4) -> f32; 6] = [
+    |p| p.w + p.x,
+    |p| p.w - p.x,
+    |p| p.w + p.y,
+    |p| p.w - p.y,
+    |p| p.w + p.z,
+    |p| p.w - p.z,
+];
+
+/// Data carried along with the clip-space positions of clipped polygons.
+pub trait Interpolate: Copy {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for Vertex {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+/// Nothing to interpolate, for polygons clipped by position alone.
+impl Interpolate for () {
+    fn interpolate(&self, _other: &Self, _t: f32) -> Self {}
+}
+
+/// Sutherland-Hodgman clipping of a convex polygon, given as vertices paired
+/// with their clip-space positions, against the view volume. Attributes of
+/// the vertices created on the planes are interpolated linearly in clip
+/// space, which keeps them perspective-correct. Returns an empty list when
+/// the polygon is not visible at all.
+pub fn clip_polygon<T: Interpolate>(mut polygon: Vec<(T, Vector4)>) -> Vec<(T, Vector4)> {
+    for distance in PLANES {
+        if polygon.is_empty() {
+            break;
+        }
+        let input = std::mem::take(&mut polygon);
+        for (i, &(vertex, position)) in input.iter().enumerate() {
+            let (prev_vertex, prev_position) = input[(i + input.len() - 1) % input.len()];
+            let (d_prev, d) = (distance(prev_position), distance(position));
+            if (d_prev >= 0.0) != (d >= 0.0) {
+                let t = d_prev / (d_prev - d);
+                polygon.push((
+                    prev_vertex.interpolate(&vertex, t),
+                    prev_position.lerp(position, t),
+                ));
+            }
+            if d >= 0.0 {
+                polygon.push((vertex, position));
+            }
+        }
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vector3;
+
+    /// Vertex whose color records the clip-space x of its position.
+    fn vertex(position: Vector4) -> (Vertex, Vector4) {
+        let zero = Vector3::default();
+        let vertex = Vertex {
+            model: zero,
+            model_normal: zero,
+            world: zero,
+            normal: zero,
+            position: [0, 0],
+            depth: 0.0,
+            inv_w: 1.0,
+            color: Vector3::new(position.x, 0.0, 0.0),
+        };
+        (vertex, position)
+    }
+
+    #[test]
+    fn triangle_straddling_a_plane_gains_a_vertex() {
+        let triangle = vec![
+            vertex(Vector4::new(0.0, 0.0, 0.0, 1.0)),
+            vertex(Vector4::new(2.0, 0.0, 0.0, 1.0)),
+            vertex(Vector4::new(0.0, 1.0, 0.0, 1.0)),
+        ];
+        let clipped = clip_polygon(triangle);
+        assert_eq!(clipped.len(), 4);
+        for (vertex, position) in &clipped {
+            assert!(position.x <= position.w + 1e-6);
+            assert!((vertex.color.x - position.x).abs() < 1e-6);
+        }
+        let on_plane = clipped
+            .iter()
+            .filter(|(_, position)| (position.x - 1.0).abs() < 1e-6)
+            .map(|(_, position)| position.y)
+            .collect::<Vec<f32>>();
+        assert_eq!(on_plane.len(), 2);
+        assert!(on_plane.contains(&0.0) && on_plane.contains(&0.5));
+    }
+
+    #[test]
+    fn polygons_inside_are_kept_and_outside_dropped() {
+        let inside = vec![
+            vertex(Vector4::new(0.0, 0.0, 0.0, 1.0)),
+            vertex(Vector4::new(0.5, 0.0, 0.0, 1.0)),
+            vertex(Vector4::new(0.0, 0.5, 0.0, 1.0)),
+        ];
+        assert_eq!(clip_polygon(inside).len(), 3);
+
+        let behind_near = vec![
+            ((), Vector4::new(0.0, 0.0, -2.0, 1.0)),
+            ((), Vector4::new(0.5, 0.0, -2.0, 1.0)),
+            ((), Vector4::new(0.0, 0.5, -3.0, 1.0)),
+        ];
+        assert!(clip_polygon(behind_near).is_empty());
+    }
+}
@@ -1,37 +1,253 @@
-use rand::prelude::*;
-#[derive(Debug)]
+/// Rule deciding which regions enclosed by a polygon outline are inside,
+/// only matters for self-intersecting or overlapping outlines.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum FillRule {
+    /// A point is inside when a ray from it crosses the outline an odd
+    /// number of times.
+    EvenOdd,
+    /// A point is inside when the outline winds around it at least once.
+    NonZero,
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Edge {
-    pub max: f32,
-    pub min: f32,
-    pub inv: f32,
-    pub id: i32,
+    /// First scanline below the edge, edges cover the rows `y_min..y_max` so
+    /// neighbouring edges never both count on their shared vertex row.
+    pub y_max: i32,
+    /// Intersection of the edge with the current scanline.
+    pub x: f32,
+    /// Upper end and extent of the edge, intersections are computed from
+    /// them rather than accumulated so they land exactly on pixel centers.
+    origin: [i32; 2],
+    delta: [i32; 2],
+    /// +1 for edges going down the screen, -1 for edges going up.
+    pub winding: i32,
 }
 
-impl PartialEq for Edge {
-    fn eq(&self, rhs: &Edge) -> bool {
-        self.id == rhs.id
+impl Edge {
+    /// Edge between two screen positions, `None` for horizontal edges which
+    /// never cross a scanline.
+    pub fn new(p1: [i32; 2], p2: [i32; 2]) -> Option<Edge> {
+        if p1[1] == p2[1] {
+            return None;
+        }
+        let (lower, upper, winding) = if p1[1] < p2[1] {
+            (p1, p2, 1)
+        } else {
+            (p2, p1, -1)
+        };
+        Some(Edge {
+            y_max: upper[1],
+            x: lower[0] as f32,
+            origin: lower,
+            delta: [upper[0] - lower[0], upper[1] - lower[1]],
+            winding,
+        })
+    }
+
+    /// Intersection with scanline `y`.
+    pub fn x_at(&self, y: i32) -> f32 {
+        let offset = (y - self.origin[1]) as i64 * self.delta[0] as i64;
+        self.origin[0] as f32 + offset as f32 / self.delta[1] as f32
     }
 }
 
-impl Edge {
-    pub fn new(v1: usize, v2: usize, positions: &[[i32; 3]]) -> Edge {
-        Edge {
-            min: if positions[v1][1] > positions[v2][1] {
-                positions[v2][0] as f32
-            } else {
-                positions[v1][0] as f32
-            },
-            max: positions[v1][0].max(positions[v2][0]) as f32,
-            id: {
-                let mut rng = rand::thread_rng();
-                rng.gen()
-            },
-            inv: if positions[v1][1] == positions[v2][1] {
-                0.0
-            } else {
-                ((positions[v1][0] as f32) - (positions[v2][0] as f32))
-                    / (positions[v1][1] - positions[v2][1]) as f32
-            },
+/// Edges of a polygon bucketed by the scanline they become active on.
+pub struct EdgeTable {
+    pub top: i32,
+    pub bottom: i32,
+    buckets: Vec<Vec<Edge>>,
+}
+
+impl EdgeTable {
+    pub fn new(positions: &[[i32; 2]]) -> EdgeTable {
+        let top = positions.iter().map(|p| p[1]).min().unwrap_or(0);
+        let bottom = positions.iter().map(|p| p[1]).max().unwrap_or(-1);
+        let mut buckets = vec![vec![]; (bottom - top + 1).max(0) as usize];
+        for (i, p1) in positions.iter().enumerate() {
+            let p2 = positions[(i + 1) % positions.len()];
+            if let Some(edge) = Edge::new(*p1, p2) {
+                buckets[(p1[1].min(p2[1]) - top) as usize].push(edge);
+            }
+        }
+        EdgeTable {
+            top,
+            bottom,
+            buckets,
+        }
+    }
+
+    /// Removes and returns the edges whose upper end, the one with the smaller
+    /// image y, lies on scanline `y`.
+    pub fn take(&mut self, y: i32) -> Vec<Edge> {
+        match self.buckets.get_mut((y - self.top) as usize) {
+            Some(bucket) if y >= self.top => std::mem::take(bucket),
+            _ => vec![],
+        }
+    }
+}
+
+/// Active edge table, kept sorted by the current intersection `x`. New edges
+/// are placed by binary search, which shifts the edges after them, and ended
+/// ones are dropped while stepping to the next scanline, so removal costs
+/// nothing beyond the walk over the table every scanline does anyway.
+#[derive(Default)]
+pub struct ActiveEdgeTable {
+    pub edges: Vec<Edge>,
+}
+
+impl ActiveEdgeTable {
+    /// Adds the edges starting on the current scanline.
+    pub fn insert(&mut self, new_edges: Vec<Edge>) {
+        for edge in new_edges {
+            let position = self.edges.partition_point(|e| e.x <= edge.x);
+            self.edges.insert(position, edge);
+        }
+    }
+
+    /// Horizontal `(start, end)` spans of the current scanline lying inside
+    /// the polygon according to `rule`.
+    pub fn spans(&self, rule: FillRule) -> Vec<(f32, f32)> {
+        let mut spans = vec![];
+        let mut winding = 0;
+        let mut start = 0.0;
+        for edge in &self.edges {
+            let was_inside = winding != 0;
+            winding = match rule {
+                FillRule::EvenOdd => 1 - winding,
+                FillRule::NonZero => winding + edge.winding,
+            };
+            match (was_inside, winding != 0) {
+                (false, true) => start = edge.x,
+                (true, false) => spans.push((start, edge.x)),
+                _ => {}
+            }
+        }
+        spans
+    }
+
+    /// Moves every intersection to scanline `y + 1`, dropping the edges that
+    /// end there and restoring the order with an insertion sort since edges
+    /// swap places only where they cross.
+    pub fn advance(&mut self, y: i32) {
+        self.edges.retain(|edge| edge.y_max > y + 1);
+        for edge in self.edges.iter_mut() {
+            edge.x = edge.x_at(y + 1);
         }
+        for i in 1..self.edges.len() {
+            let mut j = i;
+            while j > 0 && self.edges[j - 1].x > self.edges[j].x {
+                self.edges.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+}
+
+/// Pixels covered by the outline `positions` according to `rule`, as
+/// `(y, start, end)` spans of the scanlines with `end` exclusive. The
+/// outline may intersect itself, e.g. a pentagram has its middle pentagon
+/// filled only under `FillRule::NonZero`.
+pub fn fill_polygon(positions: &[[i32; 2]], rule: FillRule) -> Vec<(i32, i32, i32)> {
+    let mut edge_table = EdgeTable::new(positions);
+    let mut aet = ActiveEdgeTable::default();
+    let mut spans = vec![];
+    for y in edge_table.top..edge_table.bottom {
+        aet.insert(edge_table.take(y));
+        for (start, end) in aet.spans(rule) {
+            let (start, end) = pixel_span(start, end);
+            if start < end {
+                spans.push((y, start, end));
+            }
+        }
+        aet.advance(y);
+    }
+    spans
+}
+
+/// Pixels whose centers lie in `start..end`, the left end inclusive like
+/// the top-left rule of the half-space rasterizer.
+pub fn pixel_span(start: f32, end: f32) -> (i32, i32) {
+    (start.ceil() as i32, end.ceil() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covers(spans: &[(i32, i32, i32)], x: i32, y: i32) -> bool {
+        spans
+            .iter()
+            .any(|&(sy, start, end)| sy == y && (start..end).contains(&x))
+    }
+
+    /// Five-pointed star drawn in one stroke around (50, 50).
+    fn pentagram() -> Vec<[i32; 2]> {
+        (0..5)
+            .map(|i| {
+                let angle = (-90.0 + 144.0 * i as f32).to_radians();
+                [
+                    (50.0 + 40.0 * angle.cos()).round() as i32,
+                    (50.0 + 40.0 * angle.sin()).round() as i32,
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pentagram_middle_depends_on_fill_rule() {
+        let even_odd = fill_polygon(&pentagram(), FillRule::EvenOdd);
+        let non_zero = fill_polygon(&pentagram(), FillRule::NonZero);
+        assert_ne!(even_odd, non_zero);
+
+        assert!(!covers(&even_odd, 50, 52));
+        assert!(covers(&non_zero, 50, 52));
+
+        // the points of the star are inside under both rules
+        for (x, y) in [(50, 20), (20, 40), (80, 40)] {
+            assert!(covers(&even_odd, x, y));
+            assert!(covers(&non_zero, x, y));
+        }
+        assert!(!covers(&non_zero, 50, 85));
+    }
+
+    #[test]
+    fn overlapping_loops_depend_on_fill_rule() {
+        // two squares traced in the same direction, overlapping in 10..20
+        let outline = [
+            [0, 0],
+            [20, 0],
+            [20, 20],
+            [0, 20],
+            [0, 0],
+            [10, 10],
+            [30, 10],
+            [30, 30],
+            [10, 30],
+            [10, 10],
+        ];
+        let even_odd = fill_polygon(&outline, FillRule::EvenOdd);
+        let non_zero = fill_polygon(&outline, FillRule::NonZero);
+        assert!(!covers(&even_odd, 15, 15));
+        assert!(covers(&non_zero, 15, 15));
+        assert!(covers(&even_odd, 5, 5) && covers(&non_zero, 5, 5));
+        assert!(covers(&even_odd, 25, 25) && covers(&non_zero, 25, 25));
+    }
+
+    #[test]
+    fn triangle_spans_start_on_the_top_row() {
+        let spans = fill_polygon(&[[0, 0], [10, 0], [0, 10]], FillRule::EvenOdd);
+        let expected = (0..10).map(|y| (y, 0, 10 - y)).collect::<Vec<_>>();
+        assert_eq!(spans, expected);
+    }
+
+    #[test]
+    fn simple_polygons_fill_the_same_under_both_rules() {
+        let triangle = [[0, 0], [40, 5], [10, 30]];
+        assert_eq!(
+            fill_polygon(&triangle, FillRule::EvenOdd),
+            fill_polygon(&triangle, FillRule::NonZero)
+        );
+        assert!(covers(&fill_polygon(&triangle, FillRule::EvenOdd), 15, 10));
     }
 }
@@ -2,16 +2,26 @@
 
 pub mod consts {
     pub const IMAGE_SIZE: u32 = 700;
-    pub const MAX_Z: f32 = IMAGE_SIZE as f32;
+    pub const MAX_Z: f32 = 2.0;
+    pub const MAX_KA: f32 = 1.0;
     pub const MAX_KD: f32 = 1.0;
     pub const MAX_KS: f32 = 1.0;
     pub const MAX_M: f32 = 100.0;
-    pub const ORBIT_R: f32 = 500.0;
+    pub const ORBIT_R: f32 = 1.5;
+    pub const CAMERA_ROTATION_SPEED: f32 = 0.01;
+    pub const CAMERA_ZOOM_SPEED: f32 = 0.002;
+    /// Smallest gap kept between the camera near and far planes.
+    pub const MIN_DEPTH_RANGE: f32 = 0.01;
 }
 
 mod app;
+pub mod camera;
+pub mod clipping;
 pub mod edge;
+pub mod math;
 pub mod polygon;
+pub mod renderer;
+pub mod shadow;
+pub mod transform;
 pub mod utils;
-pub mod vector;
 pub use app::PolygonFiller;
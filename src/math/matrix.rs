@@ -0,0 +1,398 @@
+use super::{Vector3, Vector4};
+use std::ops::Mul;
+
+/// Row-major 3x3 matrix acting on column vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix3 {
+    pub m: [[f32; 3]; 3],
+}
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Matrix3 {
+    pub fn identity() -> Matrix3 {
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Matrix3 { m }
+    }
+
+    pub fn transpose(&self) -> Matrix3 {
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.m[j][i];
+            }
+        }
+        Matrix3 { m }
+    }
+
+    fn cofactor(&self, row: usize, col: usize) -> f32 {
+        let (r1, r2) = ((row + 1) % 3, (row + 2) % 3);
+        let (c1, c2) = ((col + 1) % 3, (col + 2) % 3);
+        self.m[r1][c1] * self.m[r2][c2] - self.m[r1][c2] * self.m[r2][c1]
+    }
+
+    pub fn determinant(&self) -> f32 {
+        (0..3).map(|j| self.m[0][j] * self.cofactor(0, j)).sum()
+    }
+
+    /// Inverse computed from the adjugate, `None` for singular matrices.
+    pub fn inverse(&self) -> Option<Matrix3> {
+        let det = self.determinant();
+        if det == 0.0 {
+            return None;
+        }
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.cofactor(j, i) / det;
+            }
+        }
+        Some(Matrix3 { m })
+    }
+}
+
+impl Matrix4 {
+    pub fn identity() -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Matrix4 { m }
+    }
+
+    pub fn translation(offset: Vector3) -> Matrix4 {
+        let mut matrix = Matrix4::identity();
+        matrix.m[0][3] = offset.x;
+        matrix.m[1][3] = offset.y;
+        matrix.m[2][3] = offset.z;
+        matrix
+    }
+
+    pub fn scale(factors: Vector3) -> Matrix4 {
+        let mut matrix = Matrix4::identity();
+        matrix.m[0][0] = factors.x;
+        matrix.m[1][1] = factors.y;
+        matrix.m[2][2] = factors.z;
+        matrix
+    }
+
+    pub fn rotation_x(angle: f32) -> Matrix4 {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Matrix4::identity();
+        matrix.m[1] = [0.0, cos, -sin, 0.0];
+        matrix.m[2] = [0.0, sin, cos, 0.0];
+        matrix
+    }
+
+    pub fn rotation_y(angle: f32) -> Matrix4 {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Matrix4::identity();
+        matrix.m[0] = [cos, 0.0, sin, 0.0];
+        matrix.m[2] = [-sin, 0.0, cos, 0.0];
+        matrix
+    }
+
+    pub fn rotation_z(angle: f32) -> Matrix4 {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Matrix4::identity();
+        matrix.m[0] = [cos, -sin, 0.0, 0.0];
+        matrix.m[1] = [sin, cos, 0.0, 0.0];
+        matrix
+    }
+
+    /// Right-handed view matrix of an eye at `eye` looking at `target`.
+    pub fn look_at(eye: Vector3, target: Vector3, up: Vector3) -> Matrix4 {
+        let f = (target - eye).norm();
+        let s = f.cross(up).norm();
+        let u = s.cross(f);
+        Matrix4 {
+            m: [
+                [s.x, s.y, s.z, -s.dot(eye)],
+                [u.x, u.y, u.z, -u.dot(eye)],
+                [-f.x, -f.y, -f.z, f.dot(eye)],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// OpenGL style projection mapping the view frustum onto the -1..1 cube.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Matrix4 {
+        let f = 1.0 / (fov_y / 2.0).tan();
+        Matrix4 {
+            m: [
+                [f / aspect, 0.0, 0.0, 0.0],
+                [0.0, f, 0.0, 0.0],
+                [
+                    0.0,
+                    0.0,
+                    (far + near) / (near - far),
+                    2.0 * far * near / (near - far),
+                ],
+                [0.0, 0.0, -1.0, 0.0],
+            ],
+        }
+    }
+
+    /// OpenGL style projection mapping the given box onto the -1..1 cube.
+    pub fn orthographic(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> Matrix4 {
+        Matrix4 {
+            m: [
+                [
+                    2.0 / (right - left),
+                    0.0,
+                    0.0,
+                    -(right + left) / (right - left),
+                ],
+                [
+                    0.0,
+                    2.0 / (top - bottom),
+                    0.0,
+                    -(top + bottom) / (top - bottom),
+                ],
+                [0.0, 0.0, -2.0 / (far - near), -(far + near) / (far - near)],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.m[j][i];
+            }
+        }
+        Matrix4 { m }
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting, `None` for
+    /// singular matrices.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.m;
+        let mut inverse = Matrix4::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap())
+                .unwrap();
+            if a[pivot][col] == 0.0 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inverse.swap(col, pivot);
+
+            let scale = a[col][col];
+            for j in 0..4 {
+                a[col][j] /= scale;
+                inverse[col][j] /= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+        Some(Matrix4 { m: inverse })
+    }
+
+    pub fn upper_left(&self) -> Matrix3 {
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            row.copy_from_slice(&self.m[i][..3]);
+        }
+        Matrix3 { m }
+    }
+
+    /// Inverse-transpose of the upper-left 3x3 block, the matrix that keeps
+    /// transformed normals perpendicular to transformed surfaces.
+    pub fn normal_matrix(&self) -> Matrix3 {
+        self.upper_left()
+            .inverse()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or_else(Matrix3::identity)
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix3 { m }
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+    fn mul(self, v: Vector3) -> Vector3 {
+        let row = |i: usize| Vector3::from_array(self.m[i]).dot(v);
+        Vector3::new(row(0), row(1), row(2))
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+impl Mul<Vector4> for Matrix4 {
+    type Output = Vector4;
+    fn mul(self, v: Vector4) -> Vector4 {
+        let row = |i: usize| {
+            let [x, y, z, w] = self.m[i];
+            Vector4::new(x, y, z, w).dot(v)
+        };
+        Vector4::new(row(0), row(1), row(2), row(3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close4(a: Matrix4, b: Matrix4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a.m[i][j] - b.m[i][j]).abs() < 1e-4, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    fn assert_close(a: Vector3, b: Vector3) {
+        assert!((a - b).len() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn transform(matrix: Matrix4, point: Vector3) -> Vector3 {
+        (matrix * Vector4::point(point)).project()
+    }
+
+    #[test]
+    fn matrix3_inverse() {
+        let matrix = Matrix3 {
+            m: [[2.0, 0.0, 1.0], [1.0, 3.0, 0.0], [0.0, 1.0, 4.0]],
+        };
+        let product = matrix.inverse().unwrap() * matrix;
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-5);
+            }
+        }
+
+        let singular = Matrix3 {
+            m: [[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 1.0]],
+        };
+        assert_eq!(singular.determinant(), 0.0);
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn matrix4_inverse() {
+        let matrix = Matrix4::translation(Vector3::new(1.0, -2.0, 3.0))
+            * Matrix4::rotation_y(0.7)
+            * Matrix4::scale(Vector3::new(2.0, 0.5, 1.5));
+        assert_close4(matrix.inverse().unwrap() * matrix, Matrix4::identity());
+        assert_close4(matrix * matrix.inverse().unwrap(), Matrix4::identity());
+
+        let mut singular = Matrix4::identity();
+        singular.m[2] = [0.0; 4];
+        assert!(singular.inverse().is_none());
+        assert!(Matrix4::scale(Vector3::new(1.0, 0.0, 1.0))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn transpose() {
+        let matrix = Matrix4 {
+            m: [
+                [1.0, 2.0, 3.0, 4.0],
+                [5.0, 6.0, 7.0, 8.0],
+                [9.0, 10.0, 11.0, 12.0],
+                [13.0, 14.0, 15.0, 16.0],
+            ],
+        };
+        let transposed = matrix.transpose();
+        assert_eq!(transposed.m[0], [1.0, 5.0, 9.0, 13.0]);
+        assert_eq!(transposed.m[3][1], 8.0);
+        assert_eq!(transposed.transpose(), matrix);
+
+        let matrix3 = matrix.upper_left();
+        assert_eq!(matrix3.transpose().m[0], [1.0, 5.0, 9.0]);
+        assert_eq!(matrix3.transpose().transpose(), matrix3);
+    }
+
+    #[test]
+    fn look_at_moves_eye_to_origin_and_target_onto_negative_z() {
+        let eye = Vector3::new(1.0, 2.0, 3.0);
+        let target = Vector3::new(-1.0, 0.5, -2.0);
+        let view = Matrix4::look_at(eye, target, Vector3::new(0.0, 1.0, 0.0));
+        assert_close(transform(view, eye), Vector3::new(0.0, 0.0, 0.0));
+        let distance = (target - eye).len();
+        assert_close(transform(view, target), Vector3::new(0.0, 0.0, -distance));
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_unit_depth() {
+        let projection = Matrix4::perspective(1.0, 1.5, 0.5, 20.0);
+        assert!((transform(projection, Vector3::new(0.0, 0.0, -0.5)).z + 1.0).abs() < 1e-4);
+        assert!((transform(projection, Vector3::new(0.0, 0.0, -20.0)).z - 1.0).abs() < 1e-4);
+
+        // the top edge of the field of view lands on the top of the image
+        let top = Vector3::new(0.0, (0.5f32).tan() * 4.0, -4.0);
+        assert!((transform(projection, top).y - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn orthographic_maps_box_to_unit_cube() {
+        let projection = Matrix4::orthographic(-2.0, 4.0, -1.0, 3.0, 1.0, 11.0);
+        assert_close(
+            transform(projection, Vector3::new(-2.0, -1.0, -1.0)),
+            Vector3::new(-1.0, -1.0, -1.0),
+        );
+        assert_close(
+            transform(projection, Vector3::new(4.0, 3.0, -11.0)),
+            Vector3::new(1.0, 1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular() {
+        let model = Matrix4::rotation_z(0.4) * Matrix4::scale(Vector3::new(3.0, 1.0, 1.0));
+        let tangent = Vector3::new(1.0, 1.0, 0.0);
+        let normal = Vector3::new(1.0, -1.0, 0.0);
+        let tangent = (model * Vector4::direction(tangent)).xyz();
+        let normal = model.normal_matrix() * normal;
+        assert!(tangent.dot(normal).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,7 @@
+mod matrix;
+mod quaternion;
+mod vector;
+
+pub use matrix::{Matrix3, Matrix4};
+pub use quaternion::Quaternion;
+pub use vector::{Vector2, Vector3, Vector4};
@@ -0,0 +1,199 @@
+use super::{Matrix4, Vector3};
+use std::ops::{Mul, Neg};
+
+/// Rotation quaternion `w + xi + yj + zk`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quaternion {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotation by `angle` radians around `axis`.
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Quaternion {
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        let axis = axis.norm() * sin;
+        Quaternion::new(axis.x, axis.y, axis.z, cos)
+    }
+
+    /// Rotation around the x, y and z axes in that order, angles in radians.
+    pub fn from_euler(x: f32, y: f32, z: f32) -> Quaternion {
+        Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), z)
+            * Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), y)
+            * Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), x)
+    }
+
+    pub fn dot(&self, other: Quaternion) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn len(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn norm(&self) -> Quaternion {
+        let len = self.len();
+        Quaternion::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn rotate(&self, v: Vector3) -> Vector3 {
+        let u = Vector3::new(self.x, self.y, self.z);
+        let t = u.cross(v) * 2.0;
+        v + t * self.w + u.cross(t)
+    }
+
+    /// Spherical linear interpolation along the shorter arc between two unit
+    /// quaternions.
+    pub fn slerp(&self, other: Quaternion, t: f32) -> Quaternion {
+        let mut cos = self.dot(other);
+        let other = if cos < 0.0 {
+            cos = -cos;
+            -other
+        } else {
+            other
+        };
+        // nearly parallel quaternions would divide by a vanishing sine
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion::new(
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+            a * self.w + b * other.w,
+        )
+        .norm()
+    }
+
+    pub fn to_matrix(&self) -> Matrix4 {
+        let Quaternion { x, y, z, w } = self.norm();
+        let mut matrix = Matrix4::identity();
+        matrix.m[0] = [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+            0.0,
+        ];
+        matrix.m[1] = [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+            0.0,
+        ];
+        matrix.m[2] = [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+        ];
+        matrix
+    }
+}
+
+/// Hamilton product, `a * b` applies `b` first.
+impl Mul for Quaternion {
+    type Output = Self;
+    fn mul(self, o: Self) -> Self {
+        Quaternion::new(
+            self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+            self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+            self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+            self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z,
+        )
+    }
+}
+
+impl Neg for Quaternion {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Quaternion::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vector4;
+    use std::f32::consts::PI;
+
+    fn assert_same_rotation(a: Quaternion, b: Quaternion) {
+        assert!(a.dot(b).abs() > 1.0 - 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    fn assert_close(a: Vector3, b: Vector3) {
+        assert!((a - b).len() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn slerp_interpolates_rotation_angle() {
+        let axis = Vector3::new(0.0, 0.0, 1.0);
+        let a = Quaternion::from_axis_angle(axis, 0.0);
+        let b = Quaternion::from_axis_angle(axis, PI / 2.0);
+        assert_same_rotation(a.slerp(b, 0.0), a);
+        assert_same_rotation(a.slerp(b, 1.0), b);
+        assert_same_rotation(a.slerp(b, 0.5), Quaternion::from_axis_angle(axis, PI / 4.0));
+    }
+
+    #[test]
+    fn slerp_takes_shorter_arc() {
+        let axis = Vector3::new(0.0, 1.0, 0.0);
+        let a = Quaternion::from_axis_angle(axis, 0.2);
+        // the same rotation as 0.6 radians, stored with the opposite sign
+        let b = -Quaternion::from_axis_angle(axis, 0.6);
+        assert!(a.dot(b) < 0.0);
+        assert_same_rotation(a.slerp(b, 0.5), Quaternion::from_axis_angle(axis, 0.4));
+    }
+
+    #[test]
+    fn to_matrix_matches_rotate() {
+        let q = Quaternion::from_euler(0.3, -1.1, 2.0);
+        let matrix = q.to_matrix();
+        for v in [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.5, -2.0, 3.0),
+        ] {
+            assert_close((matrix * Vector4::direction(v)).xyz(), q.rotate(v));
+        }
+    }
+
+    #[test]
+    fn axis_angle_and_euler_rotations() {
+        let q = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 2.0), PI / 2.0);
+        assert_close(
+            q.rotate(Vector3::new(1.0, 0.0, 0.0)),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        assert_close(
+            (q.conjugate() * q).rotate(Vector3::new(1.0, 2.0, 3.0)),
+            Vector3::new(1.0, 2.0, 3.0),
+        );
+
+        let (x, y, z) = (0.3, -0.4, 0.5);
+        let euler = Quaternion::from_euler(x, y, z).to_matrix();
+        let matrices = Matrix4::rotation_z(z) * Matrix4::rotation_y(y) * Matrix4::rotation_x(x);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((euler.m[i][j] - matrices.m[i][j]).abs() < 1e-5);
+            }
+        }
+    }
+}
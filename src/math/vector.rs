@@ -0,0 +1,247 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vector2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vector3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vector4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Vector2 {
+    pub fn new(x: f32, y: f32) -> Vector2 {
+        Vector2 { x, y }
+    }
+
+    /// Z component of the 3D cross product, positive when `other` lies
+    /// counter-clockwise from `self`.
+    pub fn cross(&self, other: Vector2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+}
+
+impl Vector3 {
+    pub fn new(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    pub fn from_array(arr: [f32; 3]) -> Vector3 {
+        Vector3::new(arr[0], arr[1], arr[2])
+    }
+
+    pub fn cos(a: Vector3, b: Vector3) -> f32 {
+        a.dot(b) / (a.len() * b.len())
+    }
+
+    pub fn cross(&self, other: Vector3) -> Vector3 {
+        Vector3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+}
+
+impl Default for Vector3 {
+    fn default() -> Vector3 {
+        Vector3::new(0.0, 0.0, 0.0)
+    }
+}
+
+impl Vector4 {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Vector4 {
+        Vector4 { x, y, z, w }
+    }
+
+    /// Homogeneous coordinates of a point.
+    pub fn point(v: Vector3) -> Vector4 {
+        Vector4::new(v.x, v.y, v.z, 1.0)
+    }
+
+    /// Homogeneous coordinates of a direction, unaffected by translation.
+    pub fn direction(v: Vector3) -> Vector4 {
+        Vector4::new(v.x, v.y, v.z, 0.0)
+    }
+
+    pub fn xyz(&self) -> Vector3 {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    /// Divides by `w`, mapping homogeneous coordinates back to 3D.
+    pub fn project(&self) -> Vector3 {
+        self.xyz() / self.w
+    }
+}
+
+macro_rules! impl_vector {
+    ($vector:ident { $($field:ident),+ }) => {
+        impl $vector {
+            pub fn dot(&self, other: $vector) -> f32 {
+                0.0 $(+ self.$field * other.$field)+
+            }
+
+            pub fn len(&self) -> f32 {
+                self.dot(*self).sqrt()
+            }
+
+            pub fn norm(&self) -> $vector {
+                *self / self.len()
+            }
+
+            pub fn lerp(&self, other: $vector, t: f32) -> $vector {
+                *self + (other - *self) * t
+            }
+        }
+
+        impl Add for $vector {
+            type Output = Self;
+            fn add(self, other: Self) -> Self {
+                Self {
+                    $($field: self.$field + other.$field),+
+                }
+            }
+        }
+
+        impl Sub for $vector {
+            type Output = Self;
+            fn sub(self, other: Self) -> Self {
+                Self {
+                    $($field: self.$field - other.$field),+
+                }
+            }
+        }
+
+        impl Neg for $vector {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self {
+                    $($field: -self.$field),+
+                }
+            }
+        }
+
+        /// Dot product.
+        impl Mul for $vector {
+            type Output = f32;
+            fn mul(self, other: Self) -> f32 {
+                self.dot(other)
+            }
+        }
+
+        impl Mul<f32> for $vector {
+            type Output = Self;
+            fn mul(self, coeff: f32) -> Self {
+                Self {
+                    $($field: self.$field * coeff),+
+                }
+            }
+        }
+
+        impl Mul<$vector> for f32 {
+            type Output = $vector;
+            fn mul(self, vector: $vector) -> $vector {
+                vector * self
+            }
+        }
+
+        impl Div<f32> for $vector {
+            type Output = Self;
+            fn div(self, coeff: f32) -> Self {
+                Self {
+                    $($field: self.$field / coeff),+
+                }
+            }
+        }
+
+        impl PartialEq for $vector {
+            fn eq(&self, other: &Self) -> bool {
+                true $(&& self.$field == other.$field)+
+            }
+        }
+    };
+}
+
+impl_vector!(Vector2 { x, y });
+impl_vector!(Vector3 { x, y, z });
+impl_vector!(Vector4 { x, y, z, w });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_sums_componentwise_products() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(4.0, -5.0, 6.0);
+        assert_eq!(a.dot(b), 12.0);
+        assert_eq!(a * b, 12.0);
+        assert_eq!(Vector2::new(1.0, 2.0).dot(Vector2::new(3.0, 4.0)), 11.0);
+        assert_eq!(
+            Vector4::new(1.0, 2.0, 3.0, 4.0).dot(Vector4::new(5.0, 6.0, 7.0, 8.0)),
+            70.0
+        );
+    }
+
+    #[test]
+    fn cross_is_perpendicular_and_right_handed() {
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        assert_eq!(x.cross(y), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(y.cross(x), Vector3::new(0.0, 0.0, -1.0));
+
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(-2.0, 0.5, 4.0);
+        assert!(a.cross(b).dot(a).abs() < 1e-5);
+        assert!(a.cross(b).dot(b).abs() < 1e-5);
+        assert_eq!(Vector2::new(1.0, 0.0).cross(Vector2::new(0.0, 1.0)), 1.0);
+    }
+
+    #[test]
+    fn len_and_norm() {
+        let v = Vector3::new(3.0, 0.0, 4.0);
+        assert_eq!(v.len(), 5.0);
+        assert_eq!(v.norm(), Vector3::new(0.6, 0.0, 0.8));
+        assert!((Vector3::cos(v, Vector3::new(1.0, 0.0, 0.0)) - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lerp_and_arithmetic() {
+        let a = Vector3::new(0.0, 2.0, -4.0);
+        let b = Vector3::new(2.0, 4.0, 4.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Vector3::new(1.0, 3.0, 0.0));
+        assert_eq!(a + b, Vector3::new(2.0, 6.0, 0.0));
+        assert_eq!(b - a, Vector3::new(2.0, 2.0, 8.0));
+        assert_eq!(-a, Vector3::new(0.0, -2.0, 4.0));
+        assert_eq!(2.0 * b, b * 2.0);
+        assert_eq!(b / 2.0, Vector3::new(1.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn default_is_zero() {
+        assert_eq!(Vector3::default(), Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn homogeneous_coordinates() {
+        let v = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(Vector4::point(v).w, 1.0);
+        assert_eq!(Vector4::direction(v).w, 0.0);
+        assert_eq!(Vector4::new(2.0, 4.0, 6.0, 2.0).project(), v);
+        assert_eq!(Vector4::point(v).xyz(), v);
+    }
+}
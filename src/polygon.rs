@@ -1,31 +1,216 @@
-use crate::vector::Vector3;
+use crate::math::Vector3;
+use crate::utils::{get_next, get_prev};
 
+#[derive(Clone)]
 pub struct Polygon {
     pub vertices: Vec<Vertex>,
+    /// Light reflected at each vertex by every light, kept apart from
+    /// `Vertex::color` by the renderer so shadows can be tested per pixel.
+    pub light_colors: Vec<[Vector3; 3]>,
 }
 
 impl Polygon {
-    pub fn get_sorted_indeces(&self) -> Vec<usize> {
-        let mut sort_vec = self
-            .vertices
+    /// Twice the signed screen-space area, positive when the vertices wind
+    /// clockwise on screen (the image y axis points down).
+    pub fn signed_area(&self) -> i64 {
+        (0..self.vertices.len())
+            .map(|i| {
+                let [x1, y1] = self.vertices[i].position;
+                let [x2, y2] = self.vertices[(i + 1) % self.vertices.len()].position;
+                x1 as i64 * y2 as i64 - x2 as i64 * y1 as i64
+            })
+            .sum()
+    }
+
+    /// Splits the polygon into triangles by ear clipping, which also handles
+    /// concave faces. Triangles keep the winding of the original outline.
+    pub fn triangulate(self) -> Vec<Polygon> {
+        if self.vertices.len() < 3 {
+            return vec![];
+        }
+        if self.vertices.len() == 3 {
+            return vec![self];
+        }
+
+        let points = self.project_to_plane();
+        let orientation = (0..points.len())
+            .map(|i| cross(points[i], points[get_next(i, points.len())], [0.0, 0.0]))
+            .sum::<f64>()
+            .signum();
+        let mut remaining: Vec<usize> = (0..points.len()).collect();
+        let mut triangles = vec![];
+        while remaining.len() > 3 {
+            let count = remaining.len();
+            let corner = |i: usize| {
+                (
+                    remaining[get_prev(i, count)],
+                    remaining[i],
+                    remaining[get_next(i, count)],
+                )
+            };
+            let is_ear = |i: usize| {
+                let (a, b, c) = corner(i);
+                cross(points[a], points[b], points[c]) * orientation > 0.0
+                    && remaining.iter().all(|&p| {
+                        p == a
+                            || p == b
+                            || p == c
+                            || !in_triangle(points[p], [a, b, c].map(|v| points[v]))
+                    })
+            };
+            // Degenerate or self-intersecting outlines may have no ear left,
+            // clipping any corner still guarantees termination.
+            let ear = (0..count).find(|&i| is_ear(i)).unwrap_or(0);
+            let (a, b, c) = corner(ear);
+            triangles.push([a, b, c]);
+            remaining.remove(ear);
+        }
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+
+        triangles
+            .iter()
+            .map(|triangle| Polygon {
+                vertices: triangle.iter().map(|&i| self.vertices[i]).collect(),
+                light_colors: vec![],
+            })
+            .collect()
+    }
+
+    /// Drops the dominant axis of the Newell normal, giving 2D coordinates in
+    /// which the outline is not degenerate.
+    fn project_to_plane(&self) -> Vec<[f64; 2]> {
+        let mut normal = [0.0f64; 3];
+        for i in 0..self.vertices.len() {
+            let p = self.vertices[i].model;
+            let q = self.vertices[get_next(i, self.vertices.len())].model;
+            normal[0] += ((p.y - q.y) * (p.z + q.z)) as f64;
+            normal[1] += ((p.z - q.z) * (p.x + q.x)) as f64;
+            normal[2] += ((p.x - q.x) * (p.y + q.y)) as f64;
+        }
+        let (u, v) = if normal[0].abs() >= normal[1].abs() && normal[0].abs() >= normal[2].abs() {
+            (1, 2)
+        } else if normal[1].abs() >= normal[2].abs() {
+            (2, 0)
+        } else {
+            (0, 1)
+        };
+        self.vertices
             .iter()
-            .map(|v| v.position)
-            .enumerate()
-            .collect::<Vec<(usize, [i32; 3])>>();
-        sort_vec.sort_by(|(_, [x1, y1, _]), (_, [x2, y2, _])| {
-            if y1 < y2 || (y1 == y2 && x1 < x2) {
-                std::cmp::Ordering::Less
-            } else {
-                std::cmp::Ordering::Greater
-            }
-        });
-        sort_vec.iter().map(|elem| elem.0).collect()
+            .map(|vertex| {
+                let model = [vertex.model.x, vertex.model.y, vertex.model.z];
+                [model[u] as f64, model[v] as f64]
+            })
+            .collect()
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct Vertex {
-    pub position: [i32; 3],
+    /// Position in the normalized model space, see `map_point`.
+    pub model: Vector3,
+    pub model_normal: Vector3,
+    /// Position and normal after the object transform, updated by the renderer.
+    pub world: Vector3,
     pub normal: Vector3,
+    /// Pixel coordinates assigned by the renderer for the current camera.
+    pub position: [i32; 2],
+    /// Normalized device depth, -1 on the near plane and 1 on the far plane.
+    pub depth: f32,
+    /// Reciprocal of the clip-space w, used for perspective-correct interpolation.
+    pub inv_w: f32,
     pub color: Vector3,
-    pub light: Vector3,
+}
+
+impl Vertex {
+    /// Vertex at `t` along the segment to `other`. Only the attributes set
+    /// before projection are interpolated, screen-space ones are left to be
+    /// recomputed by the renderer.
+    pub fn lerp(&self, other: &Vertex, t: f32) -> Vertex {
+        Vertex {
+            model: self.model.lerp(other.model, t),
+            model_normal: self.model_normal.lerp(other.model_normal, t),
+            world: self.world.lerp(other.world, t),
+            normal: self.normal.lerp(other.normal, t),
+            color: self.color.lerp(other.color, t),
+            ..*self
+        }
+    }
+}
+
+fn cross(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - b[1]) - (b[1] - a[1]) * (c[0] - b[0])
+}
+
+fn in_triangle(p: [f64; 2], [a, b, c]: [[f64; 2]; 3]) -> bool {
+    let (d1, d2, d3) = (cross(a, b, p), cross(b, c, p), cross(c, a, p));
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[[f32; 2]]) -> Polygon {
+        let normal = Vector3::new(0.0, 0.0, 1.0);
+        Polygon {
+            vertices: points
+                .iter()
+                .map(|&[x, y]| Vertex {
+                    model: Vector3::new(x, y, 0.0),
+                    model_normal: normal,
+                    world: Vector3::new(x, y, 0.0),
+                    normal,
+                    position: [0, 0],
+                    depth: 0.0,
+                    inv_w: 1.0,
+                    color: Vector3::default(),
+                })
+                .collect(),
+            light_colors: vec![],
+        }
+    }
+
+    /// Twice the signed area of a triangle in the model xy plane.
+    fn model_area(triangle: &Polygon) -> f32 {
+        let [a, b, c] = [0, 1, 2].map(|i| triangle.vertices[i].model);
+        (b - a).cross(c - a).z
+    }
+
+    #[test]
+    fn triangulates_concave_pentagon_with_reflex_first_corner() {
+        // a square with a notch cut in at corner 0, counter-clockwise
+        let outline = [[2.0, 2.0], [0.0, 4.0], [0.0, 0.0], [4.0, 0.0], [4.0, 4.0]];
+        let triangles = polygon(&outline).triangulate();
+        assert_eq!(triangles.len(), 3);
+
+        // every triangle keeps the winding and together they cover exactly
+        // the outline, 16 minus the 4 of the notch, so none spills into it
+        assert!(triangles.iter().all(|t| model_area(t) > 0.0));
+        let area: f32 = triangles.iter().map(model_area).sum();
+        assert!((area - 24.0).abs() < 1e-4);
+
+        for triangle in &triangles {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle.vertices[i].model);
+            let centroid = (a + b + c) / 3.0;
+            let in_notch = centroid.y > 2.0 && (centroid.x - 2.0).abs() < centroid.y - 2.0;
+            assert!(
+                !in_notch,
+                "triangle centroid {:?} lies in the notch",
+                centroid
+            );
+        }
+    }
+
+    #[test]
+    fn triangles_pass_through_and_degenerate_faces_vanish() {
+        assert_eq!(
+            polygon(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])
+                .triangulate()
+                .len(),
+            1
+        );
+        assert!(polygon(&[[0.0, 0.0], [1.0, 0.0]]).triangulate().is_empty());
+    }
 }
@@ -0,0 +1,1156 @@
+use crate::camera::Camera;
+use crate::clipping::clip_polygon;
+use crate::edge::{pixel_span, ActiveEdgeTable, EdgeTable, FillRule};
+use crate::math::{Matrix3, Matrix4, Vector3, Vector4};
+use crate::polygon::*;
+use crate::shadow::{ShadowMap, Shadows};
+use crate::transform::Transform;
+use image::{Rgb32FImage, RgbaImage};
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// Number of image rows rasterized by a single worker in parallel mode.
+const BAND_HEIGHT: usize = 8;
+
+/// Largest depth difference at which an overlaid edge still counts as lying
+/// on the visible surface.
+const EDGE_DEPTH_EPSILON: f32 = 1e-4;
+
+#[derive(PartialEq, Eq)]
+pub enum InterpolationType {
+    /// One color per polygon, lit with its face normal.
+    Flat,
+    /// Gouraud shading, colors computed at vertices.
+    Color,
+    /// Phong shading, normals interpolated and lit per pixel.
+    Vector,
+}
+
+/// Lighting model used to shade the surface. All but `CookTorrance` add a
+/// specular term controlled by `ks` and `m` to the Lambertian `kd` term.
+/// No model rescales its result, so with `kd` at 1 a surface facing the light
+/// reflects the same diffuse light under every model.
+#[derive(PartialEq, Eq)]
+pub enum ReflectionModel {
+    /// Diffuse only, no highlights.
+    Lambert,
+    /// `cos(V, R)^m` with `R` the light direction mirrored about the normal.
+    Phong,
+    /// `cos(N, H)^m` with `H` the half-vector between the light and the viewer.
+    BlinnPhong,
+    /// `exp(-m * angle(N, H)^2)`, a Gaussian lobe around the half-vector.
+    Gaussian,
+    /// Physically based microfacet BRDF driven by the material metalness and
+    /// roughness, see `cook_torrance`.
+    CookTorrance,
+}
+
+impl ReflectionModel {
+    /// Specular intensity for the normal `n`, light direction `l` and view
+    /// direction `v`, none of which need to be normalized.
+    pub fn specular(&self, n: Vector3, l: Vector3, v: Vector3, m: f32) -> f32 {
+        let half = || l.norm() + v.norm();
+        match self {
+            ReflectionModel::Lambert | ReflectionModel::CookTorrance => 0.0,
+            ReflectionModel::Phong => {
+                let n = n.norm();
+                let r = n * (n * l * 2.0) - l;
+                Vector3::cos(v, r).max(0.0).powf(m)
+            }
+            ReflectionModel::BlinnPhong => Vector3::cos(n, half()).max(0.0).powf(m),
+            ReflectionModel::Gaussian => {
+                let angle = Vector3::cos(n, half()).clamp(-1.0, 1.0).acos();
+                (-m * angle * angle).exp()
+            }
+        }
+    }
+}
+
+/// Light reflected towards the viewer by a Cook-Torrance microfacet surface
+/// with a GGX normal distribution, Smith-Schlick geometry term and Schlick's
+/// Fresnel approximation. The diffuse part is reduced by the reflected energy
+/// and vanishes for metals, whose albedo tints the specular reflection
+/// instead. Light colors are treated as the irradiance at normal incidence
+/// times pi, so a white diffuse surface facing a white light is fully lit.
+pub fn cook_torrance(
+    material: &Material<'_>,
+    albedo: [f32; 3],
+    n: Vector3,
+    l: Vector3,
+    v: Vector3,
+) -> [f32; 3] {
+    let (n, l, v) = (n.norm(), l.norm(), v.norm());
+    let n_dot_l = n.dot(l);
+    if n_dot_l <= 0.0 {
+        return [0.0; 3];
+    }
+    let n_dot_v = n.dot(v).max(1e-4);
+    let h = (l + v).norm();
+    let n_dot_h = n.dot(h).max(0.0);
+    let h_dot_v = h.dot(v).max(0.0);
+
+    let roughness = material.roughness.clamp(0.05, 1.0);
+    let alpha2 = roughness.powi(4);
+    let distribution =
+        alpha2 / (std::f32::consts::PI * (n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0).powi(2));
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let geometry = |cos: f32| cos / (cos * (1.0 - k) + k);
+    let visibility = geometry(n_dot_l) * geometry(n_dot_v) / (4.0 * n_dot_l * n_dot_v);
+
+    albedo.map(|albedo| {
+        let f0 = 0.04 + (albedo - 0.04) * material.metalness;
+        let fresnel = f0 + (1.0 - f0) * (1.0 - h_dot_v).powi(5);
+        let diffuse = (1.0 - fresnel) * (1.0 - material.metalness) * albedo;
+        let specular = std::f32::consts::PI * distribution * visibility * fresnel;
+        (diffuse + specular) * n_dot_l
+    })
+}
+
+#[derive(PartialEq, Eq)]
+pub enum DepthTest {
+    Always,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl DepthTest {
+    pub fn clear_value(&self) -> f32 {
+        match self {
+            DepthTest::Less | DepthTest::LessEqual => f32::INFINITY,
+            DepthTest::Always | DepthTest::Greater | DepthTest::GreaterEqual => f32::NEG_INFINITY,
+        }
+    }
+
+    pub fn passes(&self, z: f32, stored: f32) -> bool {
+        match self {
+            DepthTest::Always => true,
+            DepthTest::Less => z < stored,
+            DepthTest::LessEqual => z <= stored,
+            DepthTest::Greater => z > stored,
+            DepthTest::GreaterEqual => z >= stored,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+}
+
+/// Screen-space winding of polygons treated as facing the viewer.
+#[derive(PartialEq, Eq)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Algorithm used to find the pixels covered by a triangle.
+#[derive(PartialEq, Eq)]
+pub enum Rasterizer {
+    /// Scanline fill driven by the active edge table.
+    Scanline,
+    /// Bounding box traversal testing the three edge functions per pixel.
+    HalfSpace,
+}
+
+/// What is drawn for every polygon of the mesh.
+#[derive(PartialEq, Eq)]
+pub enum DisplayMode {
+    Shaded,
+    /// Only the polygon edges, without hidden line removal.
+    Wireframe,
+    /// Edges drawn on top of the visible parts of the shaded mesh.
+    Overlay,
+}
+
+/// Ordered-grid supersampling: the image is rasterized at `factor` times the
+/// target resolution on each axis and box filtered down.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Supersampling {
+    Off,
+    Grid2x2,
+    Grid3x3,
+    Grid4x4,
+}
+
+impl Supersampling {
+    pub fn factor(&self) -> u32 {
+        match self {
+            Supersampling::Off => 1,
+            Supersampling::Grid2x2 => 2,
+            Supersampling::Grid3x3 => 3,
+            Supersampling::Grid4x4 => 4,
+        }
+    }
+}
+
+/// Light reaching surfaces from every direction, independently of the light
+/// sources. With `hemispheric` set it blends from `ground` for normals facing
+/// down to `sky` for normals facing up along the y axis, instead of being
+/// uniformly `color`.
+pub struct Ambient {
+    pub color: [f32; 3],
+    pub hemispheric: bool,
+    pub sky: [f32; 3],
+    pub ground: [f32; 3],
+}
+
+impl Default for Ambient {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            hemispheric: false,
+            sky: [0.6, 0.7, 1.0],
+            ground: [0.3, 0.25, 0.2],
+        }
+    }
+}
+
+impl Ambient {
+    pub fn light(&self, normal: Vector3) -> [f32; 3] {
+        if !self.hemispheric {
+            return self.color;
+        }
+        let t = (normal.norm().y + 1.0) / 2.0;
+        [0, 1, 2].map(|i| self.ground[i] + (self.sky[i] - self.ground[i]) * t)
+    }
+}
+
+/// Surface description used by the lighting model. When `texture` is set it
+/// replaces `color`, when `normal_map` is set it perturbs the mesh normals.
+pub struct Material<'a> {
+    /// Fraction of the ambient light reflected.
+    pub ka: f32,
+    pub kd: f32,
+    pub ks: f32,
+    pub m: f32,
+    pub color: [f32; 3],
+    pub texture: Option<&'a Rgb32FImage>,
+    pub normal_map: Option<&'a Rgb32FImage>,
+    /// Physically based parameters used by `ReflectionModel::CookTorrance`,
+    /// with `color` or `texture` as the albedo.
+    pub metalness: f32,
+    pub roughness: f32,
+}
+
+impl Default for Material<'_> {
+    fn default() -> Self {
+        Self {
+            ka: 0.1,
+            kd: 0.5,
+            ks: 0.5,
+            m: 50.0,
+            color: [1.0, 1.0, 1.0],
+            texture: None,
+            normal_map: None,
+            metalness: 0.0,
+            roughness: 0.5,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum LightType {
+    /// Shines from `position` in every direction.
+    Point,
+    /// Infinitely far away, shines along `direction` everywhere.
+    Directional,
+    /// Shines from `position` into a cone around `direction`.
+    Spot,
+}
+
+/// How the light of point and spot lights fades with distance.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum AttenuationModel {
+    None,
+    /// `1 / (constant + linear * d + quadratic * d^2)`, brightening the light
+    /// where the sum is below 1. A zero sum leaves the light unattenuated.
+    Polynomial,
+    /// Physical `1 / d^2` falloff smoothly brought to zero at `range`.
+    InverseSquare,
+}
+
+#[derive(Clone)]
+pub struct Attenuation {
+    pub model: AttenuationModel,
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+    pub range: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            model: AttenuationModel::None,
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.25,
+            range: 5.0,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Fraction of the light left at `distance` from the light source.
+    pub fn factor(&self, distance: f32) -> f32 {
+        match self.model {
+            AttenuationModel::None => 1.0,
+            AttenuationModel::Polynomial => {
+                let denominator =
+                    self.constant + self.linear * distance + self.quadratic * distance * distance;
+                if denominator > 0.0 {
+                    1.0 / denominator
+                } else {
+                    1.0
+                }
+            }
+            AttenuationModel::InverseSquare => {
+                // the window keeps the falloff continuous at the range limit,
+                // the minimum distance avoids the singularity at the light
+                let window = (1.0 - (distance / self.range).powi(4)).clamp(0.0, 1.0);
+                window * window / distance.max(0.01).powi(2)
+            }
+        }
+    }
+}
+
+/// Light source placed in the same world space as the transformed mesh.
+#[derive(Clone)]
+pub struct Light {
+    pub light_type: LightType,
+    /// Position of point and spot lights.
+    pub position: Vector3,
+    /// Direction the light shines in, used by directional and spot lights.
+    pub direction: Vector3,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Half-angles in degrees of the spot light cone lit at full strength
+    /// and of the cone outside of which it gives no light at all.
+    pub inner_cone: f32,
+    pub outer_cone: f32,
+    /// Exponent shaping the fade between the inner and outer cone, 1 fades
+    /// linearly in the cosine of the angle, higher values fade faster.
+    pub falloff: f32,
+    /// Fading with distance, ignored by directional lights.
+    pub attenuation: Attenuation,
+}
+
+impl Light {
+    /// Point light circling the z axis at `radius` and height `z`, with
+    /// `angle` measured clockwise from the +y axis as seen by the default
+    /// camera. It is aimed at the origin should it become a spot light.
+    pub fn on_orbit(angle: f32, radius: f32, z: f32, color: [f32; 3]) -> Light {
+        let position = Light::orbit_position(angle, radius, z);
+        Light {
+            light_type: LightType::Point,
+            position,
+            direction: -position,
+            color,
+            intensity: 1.0,
+            inner_cone: 20.0,
+            outer_cone: 30.0,
+            falloff: 1.0,
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    pub fn orbit_position(angle: f32, radius: f32, z: f32) -> Vector3 {
+        Vector3::new(radius * angle.sin(), radius * angle.cos(), z)
+    }
+
+    /// Vector from `point` towards the light and the light strength there,
+    /// `None` when the point is not lit at all.
+    pub fn incident(&self, point: Vector3) -> Option<(Vector3, f32)> {
+        let l_vec = match self.light_type {
+            LightType::Point | LightType::Spot => self.position - point,
+            LightType::Directional => -self.direction,
+        };
+        let strength = match self.light_type {
+            LightType::Point => self.intensity * self.attenuation.factor(l_vec.len()),
+            LightType::Directional => self.intensity,
+            LightType::Spot => {
+                self.intensity * self.attenuation.factor(l_vec.len()) * self.spot_factor(-l_vec)
+            }
+        };
+        (strength > 0.0).then(|| (l_vec, strength))
+    }
+
+    /// Fraction of the spot light reaching along `ray`, 1 inside the inner
+    /// cone, 0 outside the outer one and smoothly fading in between.
+    fn spot_factor(&self, ray: Vector3) -> f32 {
+        let cos = Vector3::cos(ray, self.direction);
+        let cos_inner = self.inner_cone.to_radians().cos();
+        let cos_outer = self.outer_cone.max(self.inner_cone).to_radians().cos();
+        if cos >= cos_inner {
+            return 1.0;
+        }
+        if cos <= cos_outer {
+            return 0.0;
+        }
+        let t = (cos - cos_outer) / (cos_inner - cos_outer);
+        // smoothstep removes the visible kinks at both cone borders
+        (t * t * (3.0 - 2.0 * t)).powf(self.falloff)
+    }
+}
+
+/// Software rasterizer producing a `width` x `height` image of a triangle
+/// mesh, e.g. as returned by `load_polygons`, seen through `camera`.
+pub struct Renderer {
+    pub width: u32,
+    pub height: u32,
+    pub camera: Camera,
+    pub transform: Transform,
+    pub ambient: Ambient,
+    pub interpolation: InterpolationType,
+    pub reflection_model: ReflectionModel,
+    pub depth_test: DepthTest,
+    pub parallel: bool,
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    pub supersampling: Supersampling,
+    pub rasterizer: Rasterizer,
+    pub display_mode: DisplayMode,
+    pub wireframe_color: [f32; 3],
+    /// Edge thickness in pixels of the final image.
+    pub wireframe_width: f32,
+    pub shadows: Shadows,
+}
+
+impl Renderer {
+    pub fn new(width: u32, height: u32) -> Renderer {
+        Renderer {
+            width,
+            height,
+            camera: Camera::default(),
+            transform: Transform::default(),
+            ambient: Ambient::default(),
+            interpolation: InterpolationType::Vector,
+            reflection_model: ReflectionModel::Phong,
+            depth_test: DepthTest::Less,
+            parallel: true,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::CounterClockwise,
+            supersampling: Supersampling::Off,
+            rasterizer: Rasterizer::Scanline,
+            display_mode: DisplayMode::Shaded,
+            wireframe_color: [1.0, 1.0, 1.0],
+            wireframe_width: 1.0,
+            shadows: Shadows::default(),
+        }
+    }
+
+    pub fn is_culled(&self, polygon: &Polygon) -> bool {
+        let area = polygon.signed_area();
+        let front = match self.front_face {
+            FrontFace::Clockwise => area > 0,
+            FrontFace::CounterClockwise => area < 0,
+        };
+        match self.cull_mode {
+            CullMode::None => false,
+            CullMode::Back => !front,
+            CullMode::Front => front,
+        }
+    }
+
+    pub fn render(&self, mesh: &[Polygon], material: &Material<'_>, lights: &[Light]) -> RgbaImage {
+        let factor = self.supersampling.factor();
+        let samples = self.rasterize(
+            self.width * factor,
+            self.height * factor,
+            mesh,
+            material,
+            lights,
+        );
+        match factor {
+            1 => samples,
+            _ => resolve(&samples, factor),
+        }
+    }
+
+    fn rasterize(
+        &self,
+        width: u32,
+        height: u32,
+        mesh: &[Polygon],
+        material: &Material<'_>,
+        lights: &[Light],
+    ) -> RgbaImage {
+        let model = self.transform.matrix();
+        let pass = RenderPass {
+            renderer: self,
+            width,
+            height,
+            model,
+            normal_matrix: model.normal_matrix(),
+            view_projection: self.camera.projection_matrix(width as f32 / height as f32)
+                * self.camera.view_matrix(),
+            material,
+            lights,
+            shadow_maps: self.shadow_maps(mesh, &model, lights),
+            line_width: self.wireframe_width * (width / self.width) as f32,
+        };
+        let mut map = RgbaImage::new(width, height);
+        let mut depth = vec![self.depth_test.clear_value(); (width * height) as usize];
+        let width = width as usize;
+
+        let pass = &pass;
+        let prepare = |polygon: &Polygon| {
+            let polygon = pass.place(polygon);
+            // a face split by clipping still gets a single flat color
+            let face =
+                (self.interpolation == InterpolationType::Flat).then(|| pass.face_sample(&polygon));
+            pass.project(&polygon)
+                .into_iter()
+                .filter(|polygon| !self.is_culled(polygon))
+                .map(move |polygon| pass.shade(polygon, face))
+        };
+        let shaded = match self.parallel {
+            true => mesh.par_iter().flat_map_iter(prepare).collect(),
+            false => mesh.iter().flat_map(prepare).collect::<Vec<Polygon>>(),
+        };
+        let edges = match self.display_mode {
+            DisplayMode::Shaded => vec![],
+            _ => unique_edges(&shaded),
+        };
+        if self.parallel {
+            map.par_chunks_mut(BAND_HEIGHT * width * 4)
+                .zip(depth.par_chunks_mut(BAND_HEIGHT * width))
+                .enumerate()
+                .for_each(|(i, (color, depth))| {
+                    let mut band = Band {
+                        top: (i * BAND_HEIGHT) as i32,
+                        width,
+                        color,
+                        depth,
+                    };
+                    pass.draw(&shaded, &edges, &mut band);
+                });
+        } else {
+            let mut band = Band {
+                top: 0,
+                width,
+                color: &mut map,
+                depth: &mut depth,
+            };
+            pass.draw(&shaded, &edges, &mut band);
+        }
+        map
+    }
+
+    /// Depth maps of the mesh seen from every light, `None` for lights that
+    /// cast no shadows.
+    fn shadow_maps(
+        &self,
+        mesh: &[Polygon],
+        model: &Matrix4,
+        lights: &[Light],
+    ) -> Vec<Option<ShadowMap>> {
+        if !self.shadows.enabled {
+            return vec![];
+        }
+        let polygons = mesh
+            .iter()
+            .map(|polygon| {
+                polygon
+                    .vertices
+                    .iter()
+                    .map(|vertex| (*model * Vector4::point(vertex.model)).project())
+                    .collect()
+            })
+            .collect::<Vec<Vec<Vector3>>>();
+        let build = |light: &Light| ShadowMap::new(light, &polygons, self.shadows.map_size);
+        match self.parallel {
+            true => lights.par_iter().map(build).collect(),
+            false => lights.iter().map(build).collect(),
+        }
+    }
+}
+
+/// Edges of the projected triangles, each shared edge listed only once so
+/// its anti-aliased fringe is not blended twice.
+fn unique_edges(mesh: &[Polygon]) -> Vec<(Vertex, Vertex)> {
+    let mut seen = HashSet::new();
+    let mut edges = vec![];
+    for polygon in mesh {
+        let count = polygon.vertices.len();
+        for (i, from) in polygon.vertices.iter().enumerate() {
+            let to = polygon.vertices[(i + 1) % count];
+            let key = match from.position < to.position {
+                true => (from.position, to.position),
+                false => (to.position, from.position),
+            };
+            if seen.insert(key) {
+                edges.push((*from, to));
+            }
+        }
+    }
+    edges
+}
+
+/// Averages every `factor` x `factor` block of samples into one pixel. Colors
+/// are weighted by coverage so uncovered samples only lower the alpha.
+fn resolve(samples: &RgbaImage, factor: u32) -> RgbaImage {
+    RgbaImage::from_fn(
+        samples.width() / factor,
+        samples.height() / factor,
+        |x, y| {
+            let mut sum = [0u32; 4];
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    let [r, g, b, a] = samples.get_pixel(sx, sy).0;
+                    let a = a as u32;
+                    sum[0] += r as u32 * a;
+                    sum[1] += g as u32 * a;
+                    sum[2] += b as u32 * a;
+                    sum[3] += a;
+                }
+            }
+            if sum[3] == 0 {
+                return image::Rgba([0, 0, 0, 0]);
+            }
+            image::Rgba([
+                (sum[0] / sum[3]) as u8,
+                (sum[1] / sum[3]) as u8,
+                (sum[2] / sum[3]) as u8,
+                (sum[3] / (factor * factor)) as u8,
+            ])
+        },
+    )
+}
+
+/// Horizontal slice of the color and depth buffers starting at image row `top`.
+struct Band<'a> {
+    top: i32,
+    width: usize,
+    color: &'a mut [u8],
+    depth: &'a mut [f32],
+}
+
+impl Band<'_> {
+    fn bottom(&self) -> i32 {
+        self.top + (self.depth.len() / self.width) as i32
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y - self.top) as usize * self.width + x as usize
+    }
+}
+
+/// Normal, world position and pixel a color is computed at.
+type Sample = (Vector3, Vector3, [i32; 2]);
+
+struct RenderPass<'a> {
+    renderer: &'a Renderer,
+    width: u32,
+    height: u32,
+    model: Matrix4,
+    normal_matrix: Matrix3,
+    view_projection: Matrix4,
+    material: &'a Material<'a>,
+    lights: &'a [Light],
+    /// Aligned with `lights`, empty when shadows are disabled.
+    shadow_maps: Vec<Option<ShadowMap>>,
+    /// Wireframe thickness in pixels of the image being rasterized.
+    line_width: f32,
+}
+
+impl RenderPass<'_> {
+    fn sample(&self, image: &Rgb32FImage, x: i32, y: i32) -> [f32; 3] {
+        let u = (x.max(0) as u64 * image.width() as u64 / self.width as u64) as u32;
+        let v = (y.max(0) as u64 * image.height() as u64 / self.height as u64) as u32;
+        image
+            .get_pixel(u.min(image.width() - 1), v.min(image.height() - 1))
+            .0
+    }
+
+    /// Places the vertices in the scene with the object transform.
+    fn place(&self, polygon: &Polygon) -> Polygon {
+        let mut polygon = polygon.clone();
+        for vertex in polygon.vertices.iter_mut() {
+            vertex.world = (self.model * Vector4::point(vertex.model)).project();
+            vertex.normal = self.normal_matrix * vertex.model_normal;
+        }
+        polygon
+    }
+
+    /// Pixel a clip-space position is projected onto.
+    fn to_pixel(&self, Vector4 { x, y, w, .. }: Vector4) -> [i32; 2] {
+        [
+            ((x / w + 1.0) / 2.0 * (self.width - 1) as f32).round() as i32,
+            ((1.0 - y / w) / 2.0 * (self.height - 1) as f32).round() as i32,
+        ]
+    }
+
+    /// Transforms a placed polygon to clip space, clips it against the view
+    /// volume and maps what is left to pixels, split into a fan of triangles.
+    fn project(&self, polygon: &Polygon) -> Vec<Polygon> {
+        let clipped = clip_polygon(
+            polygon
+                .vertices
+                .iter()
+                .map(|vertex| (*vertex, self.view_projection * Vector4::point(vertex.world)))
+                .collect(),
+        );
+        let vertices = clipped
+            .into_iter()
+            .map(|(mut vertex, position)| {
+                let Vector4 { z, w, .. } = position;
+                vertex.position = self.to_pixel(position);
+                vertex.depth = z / w;
+                vertex.inv_w = 1.0 / w;
+                vertex
+            })
+            .collect::<Vec<Vertex>>();
+        (2..vertices.len())
+            .map(|i| Polygon {
+                vertices: vec![vertices[0], vertices[i - 1], vertices[i]],
+                light_colors: vec![],
+            })
+            .collect()
+    }
+
+    fn get_color(&self, n_vec: Vector3, point: Vector3, pixel: [i32; 2]) -> Vector3 {
+        self.lit_color(n_vec, point, pixel, true, |i| self.visibility(i, point))
+    }
+
+    /// Fraction of light `i` reaching `point`, 1 for lights without a
+    /// shadow map.
+    fn visibility(&self, i: usize, point: Vector3) -> f32 {
+        match self.shadow_maps.get(i) {
+            Some(Some(shadow_map)) => {
+                let shadows = &self.renderer.shadows;
+                shadow_map.visibility(point, shadows.bias, shadows.pcf_radius)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Light reflected towards the camera at `point`, including the ambient
+    /// term when `ambient` is set, with light `i` scaled by `strength(i)`.
+    fn lit_color(
+        &self,
+        n_vec: Vector3,
+        point: Vector3,
+        pixel: [i32; 2],
+        ambient: bool,
+        strength: impl Fn(usize) -> f32,
+    ) -> Vector3 {
+        let material = self.material;
+        let color = match material.texture {
+            None => material.color,
+            Some(texture) => self.sample(texture, pixel[0], pixel[1]),
+        };
+
+        let v_vec = self.renderer.camera.position - point;
+        let model = &self.renderer.reflection_model;
+        let mut rgb = match ambient {
+            true => {
+                let ambient = self.renderer.ambient.light(n_vec);
+                [0, 1, 2].map(|i| material.ka * ambient[i] * color[i])
+            }
+            false => [0.0; 3],
+        };
+        for (i, light) in self.lights.iter().enumerate() {
+            let (l_vec, incident) = match light.incident(point) {
+                Some(incident) => incident,
+                None => continue,
+            };
+            let strength = incident * strength(i);
+            if strength == 0.0 {
+                continue;
+            }
+            let reflected = match model {
+                ReflectionModel::CookTorrance => {
+                    cook_torrance(material, color, n_vec, l_vec, v_vec)
+                }
+                _ => {
+                    let diffuse = material.kd * Vector3::cos(n_vec, l_vec).max(0.0);
+                    let specular = material.ks * model.specular(n_vec, l_vec, v_vec, material.m);
+                    color.map(|c| (diffuse + specular) * c)
+                }
+            };
+            for i in 0..3 {
+                rgb[i] += reflected[i] * light.color[i] * strength;
+            }
+        }
+        Vector3::from_array(rgb)
+    }
+
+    fn get_normal_from_texture(
+        &self,
+        normal_map: &Rgb32FImage,
+        normal: Vector3,
+        pixel: [i32; 2],
+    ) -> Vector3 {
+        let rgb = self.sample(normal_map, pixel[0], pixel[1]);
+        let n_tex = Vector3::new((rgb[0] - 0.5) * 2.0, (rgb[1] - 0.5) * 2.0, rgb[2]);
+        let b_vec = if normal == Vector3::new(0.0, 0.0, 1.0) {
+            normal.cross(Vector3::new(0.0, 0.0, 1.0))
+        } else {
+            Vector3::new(0.0, 1.0, 0.0)
+        };
+        let t_vec = b_vec.cross(normal);
+        let matrix = (
+            Vector3::new(t_vec.x, b_vec.x, normal.x),
+            Vector3::new(t_vec.y, b_vec.y, normal.y),
+            Vector3::new(t_vec.z, b_vec.z, normal.z),
+        );
+        Vector3::new(matrix.0 * n_tex, matrix.1 * n_tex, matrix.2 * n_tex)
+    }
+
+    fn get_normal(&self, normal: Vector3, pixel: [i32; 2]) -> Vector3 {
+        match self.material.normal_map {
+            Some(normal_map) => self.get_normal_from_texture(normal_map, normal.norm(), pixel),
+            None => normal,
+        }
+        .norm()
+    }
+
+    /// Computes the vertex colors, all lit at `face` when given. With shadows
+    /// these hold only the ambient term and the light of every light goes to
+    /// `light_colors`, to be scaled by its visibility at each pixel.
+    fn shade(&self, mut polygon: Polygon, face: Option<Sample>) -> Polygon {
+        let samples = match face {
+            Some(face) => vec![face; polygon.vertices.len()],
+            None => polygon
+                .vertices
+                .iter()
+                .map(|vertex| self.vertex_sample(vertex))
+                .collect(),
+        };
+        if self.shadow_maps.is_empty() {
+            for (vertex, &(n_vec, point, pixel)) in polygon.vertices.iter_mut().zip(&samples) {
+                vertex.color = self.lit_color(n_vec, point, pixel, true, |_| 1.0);
+            }
+            return polygon;
+        }
+        for (vertex, &(n_vec, point, pixel)) in polygon.vertices.iter_mut().zip(&samples) {
+            vertex.color = self.lit_color(n_vec, point, pixel, true, |_| 0.0);
+        }
+        if self.renderer.interpolation != InterpolationType::Vector {
+            polygon.light_colors = (0..self.lights.len())
+                .map(|i| {
+                    [0, 1, 2].map(|v| {
+                        let (n_vec, point, pixel) = samples[v];
+                        self.lit_color(n_vec, point, pixel, false, |j| (i == j) as u8 as f32)
+                    })
+                })
+                .collect();
+        }
+        polygon
+    }
+
+    /// Where a placed face is lit: its centroid with the face normal,
+    /// oriented to agree with the vertex normals.
+    fn face_sample(&self, polygon: &Polygon) -> Sample {
+        let vertices = &polygon.vertices;
+        let count = vertices.len() as f32;
+        let centroid = vertices
+            .iter()
+            .fold(Vector3::new(0.0, 0.0, 0.0), |sum, v| sum + v.world)
+            / count;
+        let pixel = self.to_pixel(self.view_projection * Vector4::point(centroid));
+        let normal =
+            (vertices[1].world - vertices[0].world).cross(vertices[2].world - vertices[0].world);
+        let vertex_normals = vertices
+            .iter()
+            .fold(Vector3::new(0.0, 0.0, 0.0), |sum, v| sum + v.normal);
+        let normal = if normal.dot(vertex_normals) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        (self.get_normal(normal, pixel), centroid, pixel)
+    }
+
+    fn vertex_sample(&self, vertex: &Vertex) -> Sample {
+        let n_vec = self.get_normal(vertex.normal, vertex.position);
+        (n_vec, vertex.world, vertex.position)
+    }
+
+    fn paint_line(&self, spans: &[(f32, f32)], polygon: &Polygon, y: i32, band: &mut Band<'_>) {
+        let (x1, y1) = (
+            polygon.vertices[0].position[0],
+            polygon.vertices[0].position[1],
+        );
+        let (x2, y2) = (
+            polygon.vertices[1].position[0],
+            polygon.vertices[1].position[1],
+        );
+        let (x3, y3) = (
+            polygon.vertices[2].position[0],
+            polygon.vertices[2].position[1],
+        );
+        for &(start, end) in spans {
+            let (left, right) = pixel_span(start, end);
+            let (left, right) = (left.max(0), right.min(band.width as i32));
+            for x in left..right {
+                //interpolation
+                let w1 = ((y2 - y3) * (x - x3) + (x3 - x2) * (y - y3)) as f32
+                    / ((y2 - y3) * (x1 - x3) + (x3 - x2) * (y1 - y3)) as f32;
+                let w2 = ((y3 - y1) * (x - x3) + (x1 - x3) * (y - y3)) as f32
+                    / ((y2 - y3) * (x1 - x3) + (x3 - x2) * (y1 - y3)) as f32;
+                let w3 = 1.0 - w1 - w2;
+                self.paint_pixel(polygon, x, y, (w1, w2, w3), band);
+            }
+        }
+    }
+
+    /// Depth tests and shades pixel `x`, `y` of a triangle given the screen
+    /// space barycentric coordinates of its center.
+    fn paint_pixel(
+        &self,
+        polygon: &Polygon,
+        x: i32,
+        y: i32,
+        (w1, w2, w3): (f32, f32, f32),
+        band: &mut Band<'_>,
+    ) {
+        let z = polygon.vertices[0].depth * w1
+            + polygon.vertices[1].depth * w2
+            + polygon.vertices[2].depth * w3;
+        // depth is affine in screen space, other attributes are
+        // weighted by 1/w to undo the perspective division
+        let (w1, w2, w3) = {
+            let p = (
+                w1 * polygon.vertices[0].inv_w,
+                w2 * polygon.vertices[1].inv_w,
+                w3 * polygon.vertices[2].inv_w,
+            );
+            let sum = p.0 + p.1 + p.2;
+            (p.0 / sum, p.1 / sum, p.2 / sum)
+        };
+        let index = band.index(x, y);
+        if !self.renderer.depth_test.passes(z, band.depth[index]) {
+            return;
+        }
+        band.depth[index] = z;
+
+        let interpolate = |a: Vector3, b: Vector3, c: Vector3| a * w1 + b * w2 + c * w3;
+        let [v1, v2, v3] = [0, 1, 2].map(|i| &polygon.vertices[i]);
+        let point = interpolate(v1.world, v2.world, v3.world);
+        let rgb = match self.renderer.interpolation {
+            InterpolationType::Flat | InterpolationType::Color => {
+                let mut rgb = interpolate(v1.color, v2.color, v3.color);
+                for (i, [c1, c2, c3]) in polygon.light_colors.iter().enumerate() {
+                    let visibility = self.visibility(i, point);
+                    if visibility > 0.0 {
+                        rgb = rgb + interpolate(*c1, *c2, *c3) * visibility;
+                    }
+                }
+                rgb
+            }
+            InterpolationType::Vector => {
+                let true_normal = interpolate(v1.normal.norm(), v2.normal.norm(), v3.normal.norm());
+                let n_vec = self.get_normal(true_normal, [x, y]);
+                self.get_color(n_vec, point, [x, y])
+            }
+        };
+        let [r, g, b] = [rgb.x, rgb.y, rgb.z].map(|c| (c * 255.0) as u8);
+
+        band.color[index * 4..index * 4 + 4].copy_from_slice(&[r, g, b, 255]);
+    }
+
+    fn fill_polygon(&self, polygon: &Polygon, band: &mut Band<'_>) {
+        match self.renderer.rasterizer {
+            Rasterizer::Scanline => self.fill_scanline(polygon, band),
+            Rasterizer::HalfSpace => self.fill_half_space(polygon, band),
+        }
+    }
+
+    fn fill_scanline(&self, polygon: &Polygon, band: &mut Band<'_>) {
+        let positions = polygon
+            .vertices
+            .iter()
+            .map(|v| v.position)
+            .collect::<Vec<[i32; 2]>>();
+        let mut edge_table = EdgeTable::new(&positions);
+        if edge_table.bottom <= band.top || edge_table.top >= band.bottom() {
+            return;
+        }
+
+        let mut aet = ActiveEdgeTable::default();
+        for y in edge_table.top..edge_table.bottom.min(band.bottom()) {
+            aet.insert(edge_table.take(y));
+            if y >= band.top {
+                self.paint_line(&aet.spans(FillRule::EvenOdd), polygon, y, band);
+            }
+            aet.advance(y);
+        }
+    }
+
+    /// Walks the bounding box of the triangle, stepping the edge functions
+    /// incrementally. Pixels on an edge are drawn only for top and left edges
+    /// so triangles sharing an edge never cover a pixel twice.
+    fn fill_half_space(&self, polygon: &Polygon, band: &mut Band<'_>) {
+        let [p1, p2, p3] = [0, 1, 2].map(|i| {
+            let [x, y] = polygon.vertices[i].position;
+            [x as i64, y as i64]
+        });
+        let left = p1[0].min(p2[0]).min(p3[0]).max(0);
+        let right = p1[0].max(p2[0]).max(p3[0]).min(band.width as i64 - 1);
+        let top = p1[1].min(p2[1]).min(p3[1]).max(band.top as i64);
+        let bottom = p1[1].max(p2[1]).max(p3[1]).min(band.bottom() as i64 - 1);
+        if left > right || top > bottom {
+            return;
+        }
+
+        // edge function of the edge opposite each vertex, evaluated at the
+        // top-left corner of the bounding box, oriented to be positive inside
+        let area = (p3[0] - p2[0]) * (p1[1] - p2[1]) - (p3[1] - p2[1]) * (p1[0] - p2[0]);
+        if area == 0 {
+            return;
+        }
+        let sign = area.signum();
+        let edges = [(p2, p3), (p3, p1), (p1, p2)].map(|(a, b)| {
+            let (dx, dy) = (sign * (b[0] - a[0]), sign * (b[1] - a[1]));
+            let start = dx * (top - a[1]) - dy * (left - a[0]);
+            let top_left = dy < 0 || (dy == 0 && dx > 0);
+            (start, -dy, dx, if top_left { 0 } else { 1 })
+        });
+        let area = (sign * area) as f32;
+
+        let mut row = edges.map(|(start, ..)| start);
+        for y in top..=bottom {
+            let mut e = row;
+            for x in left..=right {
+                if e.iter().zip(&edges).all(|(e, (.., bias))| *e >= *bias) {
+                    let w = (e[0] as f32 / area, e[1] as f32 / area, e[2] as f32 / area);
+                    self.paint_pixel(polygon, x as i32, y as i32, w, band);
+                }
+                for (e, (_, step_x, ..)) in e.iter_mut().zip(&edges) {
+                    *e += step_x;
+                }
+            }
+            for (e, (_, _, step_y, _)) in row.iter_mut().zip(&edges) {
+                *e += step_y;
+            }
+        }
+    }
+
+    fn draw(&self, mesh: &[Polygon], edges: &[(Vertex, Vertex)], band: &mut Band<'_>) {
+        if self.renderer.display_mode != DisplayMode::Wireframe {
+            for polygon in mesh {
+                self.fill_polygon(polygon, band);
+            }
+        }
+        if self.renderer.display_mode != DisplayMode::Shaded {
+            for (from, to) in edges {
+                self.draw_line(from, to, band);
+            }
+        }
+    }
+
+    /// Xiaolin Wu's anti-aliased line widened to `line_width`: every step
+    /// along the major axis covers the pixels overlapping the line's cross
+    /// section, partially covered ones with fractional intensity.
+    fn draw_line(&self, from: &Vertex, to: &Vertex, band: &mut Band<'_>) {
+        let point = |v: &Vertex| [v.position[0] as f32, v.position[1] as f32, v.depth];
+        let (mut a, mut b) = (point(from), point(to));
+        let steep = (b[1] - a[1]).abs() > (b[0] - a[0]).abs();
+        if steep {
+            a.swap(0, 1);
+            b.swap(0, 1);
+        }
+        if a[0] > b[0] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        let dx = b[0] - a[0];
+        let gradient = if dx == 0.0 { 0.0 } else { (b[1] - a[1]) / dx };
+        let depth_gradient = if dx == 0.0 { 0.0 } else { (b[2] - a[2]) / dx };
+        // vertical extent of a line `line_width` thick measured across it
+        let half = self.line_width / 2.0 * (1.0 + gradient * gradient).sqrt();
+
+        for major in a[0] as i32..=b[0] as i32 {
+            let step = major as f32 - a[0];
+            let center = a[1] + gradient * step;
+            let z = a[2] + depth_gradient * step;
+            let (low, high) = (center - half, center + half);
+            for minor in (low - 0.5).floor() as i32..=(high + 0.5).ceil() as i32 {
+                let coverage = high.min(minor as f32 + 0.5) - low.max(minor as f32 - 0.5);
+                if coverage <= 0.0 {
+                    continue;
+                }
+                let (x, y) = if steep {
+                    (minor, major)
+                } else {
+                    (major, minor)
+                };
+                self.plot(x, y, z, coverage.min(1.0), band);
+            }
+        }
+    }
+
+    /// Blends the wireframe color over pixel `x`, `y` with opacity `alpha`.
+    fn plot(&self, x: i32, y: i32, z: f32, alpha: f32, band: &mut Band<'_>) {
+        if x < 0 || x >= band.width as i32 || y < band.top || y >= band.bottom() {
+            return;
+        }
+        let index = band.index(x, y);
+        if self.renderer.display_mode == DisplayMode::Overlay {
+            let stored = band.depth[index];
+            let visible = self.renderer.depth_test.passes(z, stored)
+                || (z - stored).abs() <= EDGE_DEPTH_EPSILON;
+            if !visible {
+                return;
+            }
+        }
+
+        let pixel = &mut band.color[index * 4..index * 4 + 4];
+        let old_alpha = pixel[3] as f32 / 255.0;
+        let new_alpha = alpha + old_alpha * (1.0 - alpha);
+        for (channel, wire) in pixel.iter_mut().zip(self.renderer.wireframe_color) {
+            let blended = wire * alpha + *channel as f32 / 255.0 * old_alpha * (1.0 - alpha);
+            *channel = (blended / new_alpha * 255.0) as u8;
+        }
+        pixel[3] = (new_alpha * 255.0) as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::load_polygons;
+
+    #[test]
+    fn scanline_and_half_space_cover_the_same_pixels() {
+        let mesh = load_polygons("assets/sphere.obj");
+        let material = Material {
+            ka: 0.1,
+            kd: 0.5,
+            ks: 0.5,
+            m: 50.0,
+            color: [1.0, 1.0, 1.0],
+            texture: None,
+            normal_map: None,
+            metalness: 0.0,
+            roughness: 0.5,
+        };
+        let lights = [Light::on_orbit(0.8, 1.5, 1.0, [1.0, 1.0, 1.0])];
+        let mut renderer = Renderer::new(101, 101);
+        renderer.transform.rotation = Vector3::new(20.0, 30.0, 40.0);
+        let scanline = renderer.render(&mesh, &material, &lights);
+        renderer.rasterizer = Rasterizer::HalfSpace;
+        let half_space = renderer.render(&mesh, &material, &lights);
+        assert!(scanline.pixels().any(|p| p.0[3] != 0));
+        assert!(scanline == half_space);
+    }
+
+    #[test]
+    fn polynomial_attenuation_follows_its_formula() {
+        let attenuation = Attenuation {
+            model: AttenuationModel::Polynomial,
+            constant: 0.5,
+            linear: 0.5,
+            quadratic: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(attenuation.factor(0.0), 2.0);
+        assert_eq!(attenuation.factor(3.0), 0.5);
+    }
+}
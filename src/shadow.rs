@@ -0,0 +1,203 @@
+use crate::clipping::clip_polygon;
+use crate::math::{Matrix4, Vector3, Vector4};
+use crate::renderer::{Light, LightType};
+
+/// Shadow mapping settings of the renderer.
+pub struct Shadows {
+    pub enabled: bool,
+    /// Width and height of the depth map rendered for every light.
+    pub map_size: u32,
+    /// Offset subtracted from the depth of shaded points before comparing
+    /// them with the map, in normalized light depth units. Too small values
+    /// make lit surfaces shadow themselves, too large ones detach shadows.
+    pub bias: f32,
+    /// Percentage-closer filtering averages the depth test over a square of
+    /// `2 * pcf_radius + 1` texels, 0 gives hard shadow edges.
+    pub pcf_radius: u32,
+}
+
+impl Default for Shadows {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            map_size: 1024,
+            bias: 0.005,
+            pcf_radius: 1,
+        }
+    }
+}
+
+/// Depth of the scene as seen from a light.
+pub struct ShadowMap {
+    view_projection: Matrix4,
+    size: usize,
+    depth: Vec<f32>,
+}
+
+impl ShadowMap {
+    /// Renders the depth of `polygons`, given by their world positions, as
+    /// seen from `light`. Returns `None` for point lights inside the scene,
+    /// which a single map cannot cover.
+    pub fn new(light: &Light, polygons: &[Vec<Vector3>], size: u32) -> Option<ShadowMap> {
+        let (center, radius) = bounding_sphere(polygons)?;
+        let mut map = ShadowMap {
+            view_projection: light_view_projection(light, center, radius)?,
+            size: size as usize,
+            depth: vec![f32::INFINITY; (size * size) as usize],
+        };
+        for polygon in polygons {
+            map.fill_polygon(polygon);
+        }
+        Some(map)
+    }
+
+    fn to_texels(&self, position: Vector4) -> Vector3 {
+        let ndc = position.project();
+        let scale = (self.size - 1) as f32;
+        Vector3::new(
+            (ndc.x + 1.0) / 2.0 * scale,
+            (1.0 - ndc.y) / 2.0 * scale,
+            ndc.z,
+        )
+    }
+
+    fn fill_polygon(&mut self, polygon: &[Vector3]) {
+        let clipped = clip_polygon(
+            polygon
+                .iter()
+                .map(|p| ((), self.view_projection * Vector4::point(*p)))
+                .collect(),
+        );
+        let points = clipped
+            .into_iter()
+            .map(|(_, position)| self.to_texels(position))
+            .collect::<Vec<Vector3>>();
+        for i in 2..points.len() {
+            self.fill_triangle([points[0], points[i - 1], points[i]]);
+        }
+    }
+
+    /// Keeps the nearest depth of every texel whose center the triangle
+    /// covers, without any fill rule since writing a texel twice is harmless.
+    fn fill_triangle(&mut self, [a, b, c]: [Vector3; 3]) {
+        let edge = |p: Vector3, q: Vector3, x: f32, y: f32| {
+            (q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x)
+        };
+        let area = edge(a, b, c.x, c.y);
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+        let last = (self.size - 1) as f32;
+        let left = a.x.min(b.x).min(c.x).max(0.0).ceil() as usize;
+        let right = a.x.max(b.x).max(c.x).min(last).floor() as usize;
+        let top = a.y.min(b.y).min(c.y).max(0.0).ceil() as usize;
+        let bottom = a.y.max(b.y).max(c.y).min(last).floor() as usize;
+        for y in top..=bottom {
+            for x in left..=right {
+                let (px, py) = (x as f32, y as f32);
+                let w = [
+                    edge(b, c, px, py) / area,
+                    edge(c, a, px, py) / area,
+                    edge(a, b, px, py) / area,
+                ];
+                if w.iter().any(|w| *w < 0.0) {
+                    continue;
+                }
+                let z = w[0] * a.z + w[1] * b.z + w[2] * c.z;
+                let stored = &mut self.depth[y * self.size + x];
+                *stored = stored.min(z);
+            }
+        }
+    }
+
+    /// Fraction of the light reaching `point`, 0 in full shadow and 1 when
+    /// fully lit or outside of the map.
+    pub fn visibility(&self, point: Vector3, bias: f32, pcf_radius: u32) -> f32 {
+        let position = self.view_projection * Vector4::point(point);
+        if position.w <= 0.0 {
+            return 1.0;
+        }
+        let texel = self.to_texels(position);
+        let (x, y) = (texel.x.round() as i64, texel.y.round() as i64);
+        let size = self.size as i64;
+        if !(0..size).contains(&x) || !(0..size).contains(&y) {
+            return 1.0;
+        }
+
+        let radius = pcf_radius as i64;
+        let (mut lit, mut samples) = (0, 0);
+        for sy in (y - radius).max(0)..=(y + radius).min(size - 1) {
+            for sx in (x - radius).max(0)..=(x + radius).min(size - 1) {
+                samples += 1;
+                if texel.z - bias <= self.depth[(sy * size + sx) as usize] {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / samples as f32
+    }
+}
+
+/// Center and radius of a sphere enclosing every polygon, centered on their
+/// bounding box.
+fn bounding_sphere(polygons: &[Vec<Vector3>]) -> Option<(Vector3, f32)> {
+    let mut points = polygons.iter().flatten();
+    let first = *points.next()?;
+    let (min, max) = points.fold((first, first), |(min, max), p| {
+        (
+            Vector3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+            Vector3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+        )
+    });
+    let center = (min + max) / 2.0;
+    let radius = polygons
+        .iter()
+        .flatten()
+        .map(|p| (*p - center).len())
+        .fold(1e-3, f32::max);
+    Some((center, radius))
+}
+
+/// Projection fitted around the scene bounding sphere: a perspective one
+/// from point and spot lights, an orthographic one along directional lights.
+fn light_view_projection(light: &Light, center: Vector3, radius: f32) -> Option<Matrix4> {
+    let (eye, forward) = match light.light_type {
+        LightType::Directional => {
+            let forward = light.direction.norm();
+            (center - forward * (2.0 * radius), forward)
+        }
+        LightType::Point => (light.position, (center - light.position).norm()),
+        LightType::Spot => (light.position, light.direction.norm()),
+    };
+    if forward.x.is_nan() {
+        return None;
+    }
+    let up = if forward.y.abs() > 0.99 {
+        Vector3::new(1.0, 0.0, 0.0)
+    } else {
+        Vector3::new(0.0, 1.0, 0.0)
+    };
+    let view = Matrix4::look_at(eye, eye + forward, up);
+
+    let distance = (center - eye).len();
+    let projection = match light.light_type {
+        LightType::Directional => {
+            Matrix4::orthographic(-radius, radius, -radius, radius, radius, 3.0 * radius)
+        }
+        LightType::Point => {
+            if distance <= radius {
+                return None;
+            }
+            let fov = 2.0 * (radius / distance).asin();
+            Matrix4::perspective(fov, 1.0, distance - radius, distance + radius)
+        }
+        LightType::Spot => {
+            let fov = (2.0 * light.outer_cone.max(light.inner_cone))
+                .clamp(1.0, 170.0)
+                .to_radians();
+            let near = (distance - radius).max(radius * 0.01);
+            Matrix4::perspective(fov, 1.0, near, distance + radius)
+        }
+    };
+    Some(projection * view)
+}
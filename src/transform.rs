@@ -0,0 +1,30 @@
+use crate::math::{Matrix4, Vector3};
+
+/// Placement of a mesh in the scene: scaled first, then rotated around the
+/// x, y and z axes in that order, then translated.
+pub struct Transform {
+    pub translation: Vector3,
+    /// Euler angles in degrees.
+    pub rotation: Vector3,
+    pub scale: Vector3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Vector3::new(0.0, 0.0, 0.0),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+    pub fn matrix(&self) -> Matrix4 {
+        Matrix4::translation(self.translation)
+            * Matrix4::rotation_z(self.rotation.z.to_radians())
+            * Matrix4::rotation_y(self.rotation.y.to_radians())
+            * Matrix4::rotation_x(self.rotation.x.to_radians())
+            * Matrix4::scale(self.scale)
+    }
+}
@@ -1,6 +1,6 @@
 use super::consts::*;
+use crate::math::Vector3;
 use crate::polygon::*;
-use crate::vector::Vector3;
 use image::io::Reader as ImageReader;
 use native_dialog::FileDialog;
 use wavefront::Obj;
@@ -16,10 +16,12 @@ pub struct MinCords {
 }
 
 pub struct CoeffData {
+    pub ka: f32,
     pub kd: f32,
     pub ks: f32,
     pub m: f32,
-    pub z: f32,
+    pub metalness: f32,
+    pub roughness: f32,
 }
 
 pub fn load_min_cords(object: &Obj) -> MinCords {
@@ -62,44 +64,48 @@ pub fn load_min_cords(object: &Obj) -> MinCords {
     min_cords
 }
 
-pub fn map_point(min_cords: MinCords, cords: [f32; 3]) -> [i32; 3] {
-    let x_range = min_cords.max_x - min_cords.min_x;
-    let y_range = min_cords.max_y - min_cords.min_y;
-    let z_range = min_cords.max_z - min_cords.min_z;
+/// Maps a model-space point into a model space centered at the origin and
+/// uniformly scaled so that the whole object fits in the -1..1 cube.
+pub fn map_point(min_cords: MinCords, cords: [f32; 3]) -> Vector3 {
+    let range = (min_cords.max_x - min_cords.min_x)
+        .max(min_cords.max_y - min_cords.min_y)
+        .max(min_cords.max_z - min_cords.min_z);
 
-    let x = (cords[0] - min_cords.min_x) / x_range * (IMAGE_SIZE as f32);
-    let y = (cords[1] - min_cords.min_y) / y_range * (IMAGE_SIZE as f32);
-    let z = (cords[2] - min_cords.min_z) / z_range * (IMAGE_SIZE as f32 / 2.0);
-    [x as i32, y as i32, z as i32]
+    Vector3::new(
+        (2.0 * cords[0] - min_cords.max_x - min_cords.min_x) / range,
+        (2.0 * cords[1] - min_cords.max_y - min_cords.min_y) / range,
+        (2.0 * cords[2] - min_cords.max_z - min_cords.min_z) / range,
+    )
 }
 
 pub fn load_polygons(file_path: &str) -> Vec<Polygon> {
-    match Obj::from_file(&file_path) {
+    match Obj::from_file(file_path) {
         Ok(o) => {
             let min_cords = load_min_cords(&o);
             o.polygons()
-                .map(|pol| {
+                .flat_map(|pol| {
                     let new_vertices: Vec<Vertex> = pol
                         .vertices()
                         .map(|v| {
-                            let positions = map_point(min_cords, v.position());
+                            let model = map_point(min_cords, v.position());
+                            let normal = v.normal().map(Vector3::from_array).unwrap_or(model);
                             Vertex {
-                                position: positions,
-                                normal: v.normal().map(Vector3::from_array).unwrap_or_else(|| {
-                                    Vector3::new(
-                                        (positions[0] - IMAGE_SIZE as i32 / 2) as f32,
-                                        (positions[1] - IMAGE_SIZE as i32 / 2) as f32,
-                                        positions[2] as f32,
-                                    )
-                                }),
-                                light: Vector3::default(),
-                                color: Vector3::default(),
+                                model,
+                                model_normal: normal,
+                                world: model,
+                                normal,
+                                position: [0, 0],
+                                depth: 0.0,
+                                inv_w: 1.0,
+                                color: Vector3::new(0.0, 0.0, 1.0),
                             }
                         })
                         .collect();
                     Polygon {
                         vertices: new_vertices,
+                        light_colors: vec![],
                     }
+                    .triangulate()
                 })
                 .collect::<Vec<Polygon>>()
         }
@@ -121,18 +127,15 @@ pub fn load_obj() -> Vec<Polygon> {
     }
 }
 
+pub fn read_image(file_path: &str, size: u32) -> image::ImageResult<image::Rgb32FImage> {
+    Ok(ImageReader::open(file_path)?
+        .decode()?
+        .resize_to_fill(size, size, image::imageops::FilterType::Nearest)
+        .to_rgb32f())
+}
+
 pub fn load_image(file_path: &str) -> image::Rgb32FImage {
-    ImageReader::open(file_path)
-        .unwrap()
-        .decode()
-        .map(|i| {
-            i.resize_to_fill(
-                IMAGE_SIZE + 1,
-                IMAGE_SIZE + 1,
-                image::imageops::FilterType::Nearest,
-            )
-            .to_rgb32f()
-        })
+    read_image(file_path, IMAGE_SIZE + 1)
         .unwrap_or_else(|_| image::Rgb32FImage::new(IMAGE_SIZE + 1, IMAGE_SIZE + 1))
 }
 